@@ -1,5 +1,5 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use drg::attacks::{count_paths, update_radius_set, GreedyParams};
+use drg::attacks::{count_paths, update_radius_set, GreedyParams, PathSampling};
 use drg::graph::*;
 use rand::Rng;
 
@@ -8,10 +8,9 @@ fn bench_update_radius(c: &mut Criterion) {
     let size = 10000;
     let deg = 4;
     let radius = 6; // points covered ~= 3^4
-    let mut graph = Graph::new(size, seed, DRGAlgo::MetaBucket(deg));
-    graph.children_project();
+    let graph = Graph::new(size, seed, DRGAlgo::MetaBucket(deg)).unwrap();
     let node = size / 2;
-    let mut inradius = NodeSet::default();
+    let mut inradius = StampedNodeSet::new(size);
     let mut p = GreedyParams {
         radius: radius,
         parallel: false,
@@ -24,13 +23,21 @@ fn bench_update_radius(c: &mut Criterion) {
     c.bench_function("update_radius parallel", |b| {
         b.iter(|| update_radius_set(&graph, node, &mut inradius, &p))
     });
+    // greedy clears the set between iterations when `reset` is set
+    p.parallel = false;
+    c.bench_function("update_radius sequential reset", |b| {
+        b.iter(|| {
+            inradius.clear();
+            update_radius_set(&graph, node, &mut inradius, &p)
+        })
+    });
 }
 
 fn bench_count_paths(c: &mut Criterion) {
     let seed = rand::thread_rng().gen::<[u8; 32]>();
     let size = (2 as u32).pow(16) as usize;
     let degree = 4;
-    let graph = Graph::new(size, seed, DRGAlgo::MetaBucket(degree));
+    let graph = Graph::new(size, seed, DRGAlgo::MetaBucket(degree)).unwrap();
     let length = 10;
     let k = 400;
     let s = ExclusionSet::new(&graph);
@@ -47,6 +54,13 @@ fn bench_count_paths(c: &mut Criterion) {
     c.bench_function("count_paths parallel", |b| {
         b.iter(|| count_paths(&graph, &s, &p))
     });
+    p.sampling = Some(PathSampling {
+        samples: size * 4,
+        seed,
+    });
+    c.bench_function("count_paths sampled", |b| {
+        b.iter(|| count_paths(&graph, &s, &p))
+    });
 }
 
 criterion_group!(benches, bench_count_paths, bench_update_radius);
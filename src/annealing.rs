@@ -0,0 +1,304 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaChaRng;
+use serde::{Deserialize, Serialize};
+
+use log::debug;
+
+use crate::attacks::{depth_reduce, AttackAlgo, GreedyParams};
+use crate::error::{Error, Result};
+use crate::graph::{ExclusionSet, Graph, Node, NodeIndex};
+
+/// Temperature at each iteration of the annealing, from hot (accepting most
+/// worsening moves) to cold (accepting almost none).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum TemperatureSchedule {
+    /// `start * rate^i`
+    Geometric { start: f64, rate: f64 },
+    /// From `start` to 0 in the iteration budget.
+    Linear { start: f64 },
+    /// `start / ln(e + i)`
+    Logarithmic { start: f64 },
+}
+
+impl TemperatureSchedule {
+    pub fn temperature(&self, iteration: usize, iterations: usize) -> f64 {
+        match *self {
+            TemperatureSchedule::Geometric { start, rate } => start * rate.powi(iteration as i32),
+            TemperatureSchedule::Linear { start } => {
+                start * (1.0 - iteration as f64 / iterations as f64)
+            }
+            TemperatureSchedule::Logarithmic { start } => {
+                start / (std::f64::consts::E + iteration as f64).ln()
+            }
+        }
+    }
+}
+
+/// Exclusion set the annealing starts from when run through `depth_reduce`
+/// (`anneal` accepts any set).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum InitialSet {
+    Empty,
+    /// Output of the Greedy attack for the same target.
+    Greedy(GreedyParams),
+    /// Output of the Valiant attack for the same target.
+    Valiant,
+}
+
+impl InitialSet {
+    pub fn build<I: NodeIndex>(&self, g: &Graph<I>, objective: Objective) -> Result<ExclusionSet> {
+        let attack = match (self, objective) {
+            (InitialSet::Empty, _) => return Ok(ExclusionSet::new(g)),
+            (InitialSet::Greedy(p), Objective::MinSize { depth }) => {
+                AttackAlgo::GreedyDepth(depth, p.clone())
+            }
+            (InitialSet::Greedy(p), Objective::MinDepth { size }) => {
+                AttackAlgo::GreedySize(size, p.clone())
+            }
+            (InitialSet::Valiant, Objective::MinSize { depth }) => AttackAlgo::ValiantDepth(depth),
+            (InitialSet::Valiant, Objective::MinDepth { size }) => AttackAlgo::ValiantSize(size),
+        };
+        depth_reduce(g, attack)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnealingParams {
+    pub iterations: usize,
+    pub schedule: TemperatureSchedule,
+    pub initial: InitialSet,
+    // cost of each unit of depth above the target relative to the cost of a
+    // node in S when minimizing |S|
+    pub penalty: f64,
+    pub seed: [u8; 32],
+}
+
+impl Default for AnnealingParams {
+    fn default() -> Self {
+        AnnealingParams {
+            iterations: 1000,
+            schedule: TemperatureSchedule::Geometric {
+                start: 2.0,
+                rate: 0.995,
+            },
+            initial: InitialSet::Empty,
+            penalty: 1.0,
+            seed: [0; 32],
+        }
+    }
+}
+
+/// What the local search optimizes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Objective {
+    /// Minimize |S| such that `depth(G-S) <= depth`.
+    MinSize { depth: usize },
+    /// Minimize `depth(G-S)` such that `|S| <= size`.
+    MinDepth { size: usize },
+}
+
+impl Objective {
+    /// Order of the solutions, the lower the better: the depth above the
+    /// target and the size for `MinSize` (so sets meeting the target always
+    /// win), the depth and the size for `MinDepth`.
+    pub fn rank(&self, size: usize, depth: usize) -> (usize, usize) {
+        match *self {
+            Objective::MinSize { depth: target } => (depth.saturating_sub(target), size),
+            Objective::MinDepth { .. } => (depth, size),
+        }
+    }
+
+    /// Value to minimize by the search, with the depth above the target
+    /// weighted by `penalty` for `MinSize`.
+    pub fn energy(&self, size: usize, depth: usize, penalty: f64) -> f64 {
+        match *self {
+            Objective::MinSize { depth: target } => {
+                size as f64 + penalty * depth.saturating_sub(target) as f64
+            }
+            Objective::MinDepth { .. } => depth as f64,
+        }
+    }
+}
+
+// Current state of the search: the set with its nodes (to pick one at random)
+// and a longest path of G-S (the only nodes worth adding).
+#[derive(Clone)]
+struct State {
+    set: ExclusionSet,
+    members: Vec<Node>,
+    path: Vec<Node>,
+}
+
+impl State {
+    fn new<I: NodeIndex>(g: &Graph<I>, set: ExclusionSet) -> Self {
+        let members = set.iter().collect();
+        let path = g.longest_path_exclude(&set);
+        State { set, members, path }
+    }
+
+    fn depth(&self) -> usize {
+        self.path.len().saturating_sub(1)
+    }
+
+    fn size(&self) -> usize {
+        self.members.len()
+    }
+
+    fn rank(&self, objective: Objective) -> (usize, usize) {
+        objective.rank(self.size(), self.depth())
+    }
+
+    fn energy(&self, objective: Objective, penalty: f64) -> f64 {
+        objective.energy(self.size(), self.depth(), penalty)
+    }
+}
+
+/// Simulated annealing over exclusion sets starting from `initial`: each
+/// iteration proposes adding a node of a longest path of G-S, removing a node
+/// of S or swapping both, always accepting improvements and accepting
+/// worsening moves with probability `exp(-delta/T)`. Returns the best set
+/// found (which might not meet the target if none did), the same for the
+/// same `p.seed` and graph spec.
+pub fn anneal<I: NodeIndex>(
+    g: &Graph<I>,
+    initial: ExclusionSet,
+    objective: Objective,
+    p: &AnnealingParams,
+) -> ExclusionSet {
+    let mut rng = ChaChaRng::from_seed(p.seed);
+    let mut current = State::new(g, initial);
+    // a size budget is a hard constraint
+    if let Objective::MinDepth { size } = objective {
+        while current.size() > size {
+            let idx = rng.gen_range(0, current.members.len());
+            let node = current.members.swap_remove(idx);
+            current.set.remove(node);
+        }
+        current.path = g.longest_path_exclude(&current.set);
+    }
+    let mut best = current.clone();
+
+    for iteration in 0..p.iterations {
+        let can_add = !current.path.is_empty()
+            && match objective {
+                Objective::MinSize { .. } => true,
+                Objective::MinDepth { size } => current.size() < size,
+            };
+        let can_remove = !current.members.is_empty();
+        // 0: add, 1: remove, 2: swap
+        let moves: Vec<usize> = [can_add, can_remove, can_remove && !current.path.is_empty()]
+            .iter()
+            .enumerate()
+            .filter(|&(_, &valid)| valid)
+            .map(|(m, _)| m)
+            .collect();
+        if moves.is_empty() {
+            break;
+        }
+
+        let mut next = current.clone();
+        let chosen = moves[rng.gen_range(0, moves.len())];
+        if chosen != 0 {
+            let idx = rng.gen_range(0, next.members.len());
+            let node = next.members.swap_remove(idx);
+            next.set.remove(node);
+        }
+        if chosen != 1 {
+            let node = current.path[rng.gen_range(0, current.path.len())];
+            if !next.set.contains(node) {
+                next.set.insert(node);
+                next.members.push(node);
+            }
+        }
+        next.path = g.longest_path_exclude(&next.set);
+
+        let delta = next.energy(objective, p.penalty) - current.energy(objective, p.penalty);
+        let temperature = p.schedule.temperature(iteration, p.iterations);
+        let accept =
+            delta <= 0.0 || (temperature > 0.0 && rng.gen::<f64>() < (-delta / temperature).exp());
+        if accept {
+            current = next;
+            if current.rank(objective) < best.rank(objective) {
+                best = current.clone();
+                debug!(
+                    "\t-> annealing iteration {}: |S| = {}, depth(G-S) = {}",
+                    iteration,
+                    best.size(),
+                    best.depth()
+                );
+            }
+        }
+    }
+    best.set
+}
+
+pub(crate) fn annealing_reduce<I: NodeIndex>(g: &Graph<I>, d: AttackAlgo) -> Result<ExclusionSet> {
+    let (objective, p) = match d {
+        AttackAlgo::AnnealingDepth(depth, p) => (Objective::MinSize { depth }, p),
+        AttackAlgo::AnnealingSize(size, p) => (Objective::MinDepth { size }, p),
+        _ => return Err(Error::InvalidAttack(Box::new(d))),
+    };
+    let initial = p.initial.build(g, objective)?;
+    Ok(anneal(g, initial, objective, &p))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::{self, DRGAlgo};
+
+    #[test]
+    fn annealing_min_size() {
+        let size = 256;
+        let depth = size / 8;
+        let g = Graph::new(size, graph::tests::TEST_SEED, DRGAlgo::MetaBucket(3)).unwrap();
+        let p = AnnealingParams {
+            iterations: 2000,
+            seed: graph::tests::TEST_SEED,
+            ..AnnealingParams::default()
+        };
+        let objective = Objective::MinSize { depth };
+        let s = anneal(&g, ExclusionSet::new(&g), objective, &p);
+        assert!(g.depth_exclude(&s) <= depth);
+        // reproducible with the same seed, also on another copy of the graph
+        assert_eq!(s, anneal(&g, ExclusionSet::new(&g), objective, &p));
+        let copy = Graph::new(size, graph::tests::TEST_SEED, DRGAlgo::MetaBucket(3)).unwrap();
+        assert_eq!(s, anneal(&copy, ExclusionSet::new(&copy), objective, &p));
+
+        // starting from a valid set never makes it worse
+        let s2 = anneal(&g, s.clone(), objective, &p);
+        assert!(g.depth_exclude(&s2) <= depth);
+        assert!(s2.size() <= s.size());
+    }
+
+    #[test]
+    fn annealing_min_depth() {
+        let size = 256;
+        let budget = size / 8;
+        let g = Graph::new(size, graph::tests::TEST_SEED, DRGAlgo::MetaBucket(3)).unwrap();
+        for &schedule in [
+            TemperatureSchedule::Geometric {
+                start: 1.0,
+                rate: 0.99,
+            },
+            TemperatureSchedule::Linear { start: 1.0 },
+            TemperatureSchedule::Logarithmic { start: 0.5 },
+        ]
+        .iter()
+        {
+            let p = AnnealingParams {
+                iterations: 500,
+                schedule,
+                initial: InitialSet::Valiant,
+                seed: graph::tests::TEST_SEED,
+                ..AnnealingParams::default()
+            };
+            let valiant = depth_reduce(&g, AttackAlgo::ValiantSize(budget)).unwrap();
+            let s = depth_reduce(&g, AttackAlgo::AnnealingSize(budget, p)).unwrap();
+            assert!(s.size() <= budget);
+            if valiant.size() <= budget {
+                assert!(g.depth_exclude(&s) <= g.depth_exclude(&valiant));
+            }
+        }
+    }
+}
@@ -1,14 +1,25 @@
+use std::cell::RefCell;
 use std::cmp::{Ordering, Reverse};
-use std::time::Instant;
+use std::collections::HashSet;
+use std::sync::atomic::{self, AtomicUsize};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use log::{debug, trace};
-use rand::SeedableRng;
+use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha20Rng;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
-use crate::graph::{EdgeSet, ExclusionSet, Graph, GraphSpec, Node, NodeSet};
-use crate::results::{AttackResults, AveragedAttackResult, SingleAttackResult};
+use crate::annealing::{annealing_reduce, AnnealingParams};
+use crate::error::{Error, Result};
+use crate::evolution::{evolution_reduce, EvolutionParams};
+use crate::graph::{
+    Edge, ExclusionSet, Graph, GraphSpec, IndexWidth, Node, NodeIndex, StampedNodeSet,
+};
+use crate::hybrid::{hybrid_reduce, Stage};
+use crate::results::{AttackResults, AveragedAttackResult, Curve, CurvePoint, SingleAttackResult};
+use crate::scoring::{NodeScorer, Scoring};
 use crate::utils;
 use rayon::prelude::*;
 
@@ -26,15 +37,30 @@ pub enum AttackAlgo {
     GreedyDepth(usize, GreedyParams),
     /// Variation of Greedy attack that has as `target` the resulting size of set S.
     GreedySize(usize, GreedyParams),
+    /// Simulated annealing minimizing the size of S for a target depth.
+    AnnealingDepth(usize, AnnealingParams),
+    /// Simulated annealing minimizing the depth for a target size of S.
+    AnnealingSize(usize, AnnealingParams),
+    /// Evolutionary search minimizing the size of S for a target depth.
+    EvolutionDepth(usize, EvolutionParams),
+    /// Evolutionary search minimizing the depth for a target size of S.
+    EvolutionSize(usize, EvolutionParams),
+    /// Sequence of stages run on the same S for a target depth.
+    HybridDepth(usize, Vec<Stage>),
 }
 
-pub fn depth_reduce(g: &mut Graph, drs: AttackAlgo) -> ExclusionSet {
+pub fn depth_reduce<I: NodeIndex>(g: &Graph<I>, drs: AttackAlgo) -> Result<ExclusionSet> {
     match drs {
         AttackAlgo::ValiantDepth(_) => valiant_reduce(g, drs),
         AttackAlgo::ValiantSize(_) => valiant_reduce(g, drs),
         AttackAlgo::ValiantAB16(_) => valiant_reduce(g, drs),
         AttackAlgo::GreedyDepth(_, _) => greedy_reduce(g, drs),
         AttackAlgo::GreedySize(_, _) => greedy_reduce(g, drs),
+        AttackAlgo::AnnealingDepth(_, _) => annealing_reduce(g, drs),
+        AttackAlgo::AnnealingSize(_, _) => annealing_reduce(g, drs),
+        AttackAlgo::EvolutionDepth(_, _) => evolution_reduce(g, drs),
+        AttackAlgo::EvolutionSize(_, _) => evolution_reduce(g, drs),
+        AttackAlgo::HybridDepth(_, _) => hybrid_reduce(g, drs),
     }
 }
 
@@ -46,6 +72,36 @@ impl fmt::Display for AttackAlgo {
             AttackAlgo::ValiantAB16(s) => write!(f, "ValiantAB16: graph remaining target {}", s),
             AttackAlgo::GreedyDepth(s, _) => write!(f, "GreedyDepth: path length {}", s),
             AttackAlgo::GreedySize(s, _) => write!(f, "GreedySize: exclusion set size {}", s),
+            AttackAlgo::AnnealingDepth(s, _) => write!(f, "AnnealingDepth: path length {}", s),
+            AttackAlgo::AnnealingSize(s, _) => {
+                write!(f, "AnnealingSize: exclusion set size {}", s)
+            }
+            AttackAlgo::EvolutionDepth(s, _) => write!(f, "EvolutionDepth: path length {}", s),
+            AttackAlgo::EvolutionSize(s, _) => {
+                write!(f, "EvolutionSize: exclusion set size {}", s)
+            }
+            AttackAlgo::HybridDepth(s, stages) => {
+                write!(f, "HybridDepth: path length {} with stages", s)?;
+                stages.iter().try_for_each(|stage| write!(f, " {}", stage))
+            }
+        }
+    }
+}
+
+impl AttackAlgo {
+    /// Same attack (with the same parameters) for another target.
+    pub fn with_target(&self, target: usize) -> AttackAlgo {
+        match self.clone() {
+            AttackAlgo::ValiantDepth(_) => AttackAlgo::ValiantDepth(target),
+            AttackAlgo::ValiantSize(_) => AttackAlgo::ValiantSize(target),
+            AttackAlgo::ValiantAB16(_) => AttackAlgo::ValiantAB16(target),
+            AttackAlgo::GreedyDepth(_, p) => AttackAlgo::GreedyDepth(target, p),
+            AttackAlgo::GreedySize(_, p) => AttackAlgo::GreedySize(target, p),
+            AttackAlgo::AnnealingDepth(_, p) => AttackAlgo::AnnealingDepth(target, p),
+            AttackAlgo::AnnealingSize(_, p) => AttackAlgo::AnnealingSize(target, p),
+            AttackAlgo::EvolutionDepth(_, p) => AttackAlgo::EvolutionDepth(target, p),
+            AttackAlgo::EvolutionSize(_, p) => AttackAlgo::EvolutionSize(target, p),
+            AttackAlgo::HybridDepth(_, s) => AttackAlgo::HybridDepth(target, s),
         }
     }
 }
@@ -69,6 +125,21 @@ pub struct AttackProfile {
     pub runs: usize,
     pub range: TargetRange,
     pub attack: AttackAlgo,
+    /// Maximum number of (target, run) attacks running at the same time
+    /// (each of them can still use the rayon pool), 1 to run them one
+    /// after the other.
+    pub concurrency: usize,
+    /// Run a single trajectory of the attack per run recording all the
+    /// targets along the way (see `attack_trajectory`) instead of one attack
+    /// per target.
+    pub warm_start: bool,
+    /// Record the depth/size curve of each run (see `depth_reduce_with_curve`),
+    /// only for the Greedy and Valiant attacks: the curve of the warm-started
+    /// trajectory, or of the attack on the farthest target otherwise.
+    pub curve: bool,
+    /// Index type to store the nodes of the graphs with, `u32` halves the
+    /// memory of the graphs that fit in it.
+    pub index: IndexWidth,
 }
 
 impl AttackProfile {
@@ -86,6 +157,11 @@ impl AttackProfile {
             AttackAlgo::ValiantAB16(size) => size as f64 / graph_size,
             AttackAlgo::GreedyDepth(depth, _) => depth as f64 / graph_size,
             AttackAlgo::GreedySize(size, _) => size as f64 / graph_size,
+            AttackAlgo::AnnealingDepth(depth, _) => depth as f64 / graph_size,
+            AttackAlgo::AnnealingSize(size, _) => size as f64 / graph_size,
+            AttackAlgo::EvolutionDepth(depth, _) => depth as f64 / graph_size,
+            AttackAlgo::EvolutionSize(size, _) => size as f64 / graph_size,
+            AttackAlgo::HybridDepth(depth, _) => depth as f64 / graph_size,
         };
         // FIXME: This code should absorb the `depth_reduce` and derived
         // functions logic. The target discrimination depth/size should
@@ -104,13 +180,33 @@ impl AttackProfile {
             runs: 1,
             range,
             attack,
+            concurrency: 1,
+            warm_start: false,
+            curve: false,
+            index: IndexWidth::Usize,
         }
     }
 }
 
-pub fn attack(g: &mut Graph, attack: AttackAlgo) -> SingleAttackResult {
+pub fn attack<I: NodeIndex>(g: &Graph<I>, attack: AttackAlgo) -> Result<SingleAttackResult> {
+    attack_curve(g, attack, None)
+}
+
+// Same as `attack`, recording the curve of the attack in `curve` if given.
+fn attack_curve<I: NodeIndex>(
+    g: &Graph<I>,
+    attack: AttackAlgo,
+    curve: Option<&mut Curve>,
+) -> Result<SingleAttackResult> {
     let start = Instant::now();
-    let set = depth_reduce(g, attack);
+    let set = match curve {
+        Some(curve) => {
+            let (set, attack_curve) = depth_reduce_with_curve(g, attack)?;
+            *curve = attack_curve;
+            set
+        }
+        None => depth_reduce(g, attack)?,
+    };
     let duration = start.elapsed();
     let depth = g.depth_exclude(&set);
     let result = SingleAttackResult {
@@ -119,11 +215,37 @@ pub fn attack(g: &mut Graph, attack: AttackAlgo) -> SingleAttackResult {
     };
     println!("{}", result);
     println!("\t-> time elapsed: {:?}", duration);
-    result
+    Ok(result)
 }
 
 // FIXME: Eventually this should replace the old `attack`.
-pub fn attack_with_profile(spec: GraphSpec, profile: &AttackProfile) -> AttackResults {
+pub fn attack_with_profile(spec: GraphSpec, profile: &AttackProfile) -> Result<AttackResults> {
+    if profile.concurrency == 0 {
+        return Err(Error::InvalidParams(
+            "concurrency must be positive".to_string(),
+        ));
+    }
+    // before generating any graph
+    let is_size = match curve_is_size(&profile.attack) {
+        Some(is_size) => is_size,
+        None if profile.curve => {
+            return Err(Error::InvalidAttack(Box::new(profile.attack.clone())))
+        }
+        None => false,
+    };
+    if profile.index.is_u32(spec.size) {
+        attack_with_index::<u32>(spec, profile, is_size)
+    } else {
+        attack_with_index::<Node>(spec, profile, is_size)
+    }
+}
+
+// `attack_with_profile` storing the nodes of the graphs with `I`.
+fn attack_with_index<I: NodeIndex>(
+    spec: GraphSpec,
+    profile: &AttackProfile,
+    is_size: bool,
+) -> Result<AttackResults> {
     let mut targets: Vec<f64> = Vec::new();
     let mut target = profile.range.start;
     loop {
@@ -137,34 +259,94 @@ pub fn attack_with_profile(spec: GraphSpec, profile: &AttackProfile) -> AttackRe
 
     let mut results: Vec<Vec<SingleAttackResult>> =
         vec![vec![SingleAttackResult::default(); profile.runs]; targets.len()];
-
-    // Iterate over the graphs first (that means iterating over each run in
-    // the outer `for`) to avoid memory bloat, we don't need to retain a
-    // graph once we attacked it with all targets.
+    let mut curves = vec![Curve::default(); if profile.curve { profile.runs } else { 0 }];
+    // the target whose attack goes the farthest, to record its curve
+    let farthest = (0..targets.len())
+        .max_by(|&i, &j| {
+            let order = targets[i].partial_cmp(&targets[j]).unwrap();
+            if is_size {
+                order
+            } else {
+                order.reverse()
+            }
+        })
+        .unwrap();
+
+    // Iterate over the graphs first (that means iterating over each batch of
+    // runs in the outer `for`) to avoid memory bloat, we don't need to retain
+    // a graph once we attacked it with all targets. The batches have enough
+    // runs to keep `concurrency` attacks busy and their graphs are generated
+    // in order from the same RNG, so they don't depend on the scheduling.
+    let attacks_per_run = if profile.warm_start { 1 } else { targets.len() };
+    let batch = std::cmp::max(1, profile.concurrency / attacks_per_run);
     let mut rng = ChaCha20Rng::from_seed(spec.seed);
-    for run in 0..profile.runs {
-        println!(
-            "Attack {}:\n\t-> Graph generation with spec {}",
-            run,
-            spec.clone()
-        );
-        let mut g = Graph::new_from_rng(spec, &mut rng);
-
-        for (t, target) in targets.iter().enumerate() {
-            let absolute_target = (target * spec.size as f64) as usize;
-            let attack_type = match profile.attack.clone() {
-                AttackAlgo::ValiantDepth(_) => AttackAlgo::ValiantDepth(absolute_target),
-                AttackAlgo::ValiantSize(_) => AttackAlgo::ValiantSize(absolute_target),
-                AttackAlgo::ValiantAB16(_) => AttackAlgo::ValiantAB16(absolute_target),
-                AttackAlgo::GreedyDepth(_, p) => AttackAlgo::GreedyDepth(absolute_target, p),
-                AttackAlgo::GreedySize(_, p) => AttackAlgo::GreedySize(absolute_target, p),
-            };
-            println!("\t-> Run target attack {:.2}, with {}", attack_type, target);
-            results[t][run] = attack(&mut g, attack_type.clone());
+    for first in (0..profile.runs).step_by(batch) {
+        let runs = first..std::cmp::min(first + batch, profile.runs);
+        let graphs = runs
+            .clone()
+            .map(|run| {
+                println!(
+                    "Attack {}:\n\t-> Graph generation with spec {}",
+                    run,
+                    spec.clone()
+                );
+                Graph::generate(spec, &mut rng)
+            })
+            .collect::<Result<Vec<Graph<I>>>>()?;
+
+        if profile.warm_start {
+            let absolute_targets: Vec<usize> = targets
+                .iter()
+                .map(|target| (target * spec.size as f64) as usize)
+                .collect();
+            let batch_results = run_concurrently(graphs.len(), profile.concurrency, |i| {
+                println!(
+                    "\t-> Run warm-started attack {}, with {} targets",
+                    profile.attack,
+                    targets.len()
+                );
+                let start = Instant::now();
+                let mut curve = Curve::default();
+                let checkpoints = attack_trajectory_curve(
+                    &graphs[i],
+                    profile.attack.clone(),
+                    &absolute_targets,
+                    Some(&mut curve).filter(|_| profile.curve),
+                )?;
+                println!("\t-> time elapsed: {:?}", start.elapsed());
+                Ok((checkpoints, curve))
+            })?;
+            for (i, (checkpoints, curve)) in batch_results.into_iter().enumerate() {
+                for (t, checkpoint) in checkpoints.iter().enumerate() {
+                    results[t][runs.start + i] = checkpoint.to_result(spec.size);
+                }
+                if profile.curve {
+                    curves[runs.start + i] = curve;
+                }
+            }
+        } else {
+            let pairs = graphs.len() * targets.len();
+            let batch_results = run_concurrently(pairs, profile.concurrency, |pair| {
+                let (g, target) = (&graphs[pair / targets.len()], targets[pair % targets.len()]);
+                let absolute_target = (target * spec.size as f64) as usize;
+                let attack_type = profile.attack.with_target(absolute_target);
+                println!("\t-> Run target attack {:.2}, with {}", attack_type, target);
+                let mut curve = Curve::default();
+                let record = profile.curve && pair % targets.len() == farthest;
+                let result = attack_curve(g, attack_type, Some(&mut curve).filter(|_| record))?;
+                Ok((result, curve))
+            })?;
+            for (pair, (result, curve)) in batch_results.into_iter().enumerate() {
+                let (t, run) = (pair % targets.len(), runs.start + pair / targets.len());
+                results[t][run] = result;
+                if profile.curve && t == farthest {
+                    curves[run] = curve;
+                }
+            }
         }
     }
 
-    AttackResults {
+    Ok(AttackResults {
         spec: spec,
         runs: profile.runs,
         attack: profile.attack.clone(),
@@ -173,9 +355,158 @@ pub fn attack_with_profile(spec: GraphSpec, profile: &AttackProfile) -> AttackRe
             .enumerate()
             .map(|(i, &target)| AveragedAttackResult::from_results(target, &results[i]))
             .collect(),
+        curves,
+    })
+}
+
+// Whether the targets of an attack with a curve (Greedy and Valiant) are
+// sizes rather than depths, `None` for the other attacks.
+fn curve_is_size(attack: &AttackAlgo) -> Option<bool> {
+    match attack {
+        AttackAlgo::ValiantSize(_) | AttackAlgo::GreedySize(_, _) => Some(true),
+        AttackAlgo::ValiantDepth(_) | AttackAlgo::GreedyDepth(_, _) => Some(false),
+        _ => None,
     }
 }
 
+/// Point of the trade-off curve of an attack: the size of S and the depth
+/// of G-S when the attack first reached `target`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub target: usize,
+    pub size: usize,
+    pub depth: usize,
+}
+
+impl Checkpoint {
+    fn to_result(self, graph_size: usize) -> SingleAttackResult {
+        SingleAttackResult {
+            depth: self.depth as f64 / graph_size as f64,
+            exclusion_size: self.size as f64 / graph_size as f64,
+        }
+    }
+}
+
+/// Warm-started version of `depth_reduce` for many `targets` (the one of
+/// `attack` is ignored): a single Greedy or Valiant trajectory extends the
+/// same S until it reaches all of them, recording a checkpoint (in the order
+/// of `targets`) the first time it reaches each one. The checkpoints are the
+/// same sets `depth_reduce` finds for each target, except for `GreedySize`
+/// which bounds `k` by the smallest target instead of each of them.
+pub fn attack_trajectory<I: NodeIndex>(
+    g: &Graph<I>,
+    attack: AttackAlgo,
+    targets: &[usize],
+) -> Result<Vec<Checkpoint>> {
+    attack_trajectory_curve(g, attack, targets, None)
+}
+
+// Same as `attack_trajectory`, recording the curve of the trajectory in
+// `curve` if given.
+fn attack_trajectory_curve<I: NodeIndex>(
+    g: &Graph<I>,
+    attack: AttackAlgo,
+    targets: &[usize],
+    curve: Option<&mut Curve>,
+) -> Result<Vec<Checkpoint>> {
+    let is_size = match curve_is_size(&attack) {
+        Some(is_size) => is_size,
+        None => return Err(Error::InvalidAttack(Box::new(attack))),
+    };
+    // the order the trajectory reaches the targets in: increasing sizes or
+    // decreasing depths
+    let mut order: Vec<usize> = (0..targets.len()).collect();
+    if is_size {
+        order.sort_by_key(|&i| targets[i]);
+    } else {
+        order.sort_by_key(|&i| Reverse(targets[i]));
+    }
+    let checkpoints = RefCell::new(Vec::with_capacity(targets.len()));
+    // record the targets `set` reaches and return if there are more left
+    let f = |set: &ExclusionSet| {
+        let mut checkpoints = checkpoints.borrow_mut();
+        let mut depth = None;
+        while let Some(&i) = order.get(checkpoints.len()) {
+            if is_size && set.size() < targets[i] {
+                break;
+            }
+            let depth = *depth.get_or_insert_with(|| g.depth_exclude(set));
+            if !is_size && depth > targets[i] {
+                break;
+            }
+            checkpoints.push(Checkpoint {
+                target: targets[i],
+                size: set.size(),
+                depth,
+            });
+        }
+        checkpoints.len() < targets.len()
+    };
+
+    match attack {
+        AttackAlgo::ValiantSize(_) | AttackAlgo::ValiantDepth(_) => {
+            valiant_reduce_main(g, &f, curve)?;
+        }
+        AttackAlgo::GreedySize(_, mut p) | AttackAlgo::GreedyDepth(_, mut p) => {
+            if is_size {
+                // same correction as `greedy_reduce_with` for all the targets
+                let smallest = targets.iter().min().copied().unwrap_or(0);
+                p.k = std::cmp::min(p.k, (smallest as f32 * 0.01).ceil() as usize);
+            }
+            let scorer = p.scoring.scorer();
+            let s = ExclusionSet::new(g);
+            greedy_reduce_main(
+                g,
+                s,
+                p,
+                scorer.as_ref(),
+                &|set: &ExclusionSet, _: &Graph<I>| f(set),
+                curve,
+            )?;
+        }
+        _ => unreachable!(),
+    }
+
+    let mut sorted = vec![None; targets.len()];
+    for (&i, checkpoint) in order.iter().zip(checkpoints.into_inner()) {
+        sorted[i] = Some(checkpoint);
+    }
+    Ok(sorted.into_iter().flatten().collect())
+}
+
+/// Run the `jobs` calls of `f` (with the index of the job) in at most
+/// `concurrency` threads, returning their results in the order of the jobs
+/// (or the error of the first one that failed) whatever order they ran in.
+fn run_concurrently<T, F>(jobs: usize, concurrency: usize, f: F) -> Result<Vec<T>>
+where
+    T: Send,
+    F: Fn(usize) -> Result<T> + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let mut done: Vec<(usize, Result<T>)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..std::cmp::min(concurrency, jobs))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let job = next.fetch_add(1, atomic::Ordering::Relaxed);
+                        if job >= jobs {
+                            return done;
+                        }
+                        done.push((job, f(job)));
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("attack thread panicked"))
+            .collect()
+    });
+    done.sort_by_key(|&(job, _)| job);
+    done.into_iter().map(|(_, result)| result).collect()
+}
+
 // GreedyParams holds the different parameters to choose for the greedy algorithm
 // such as the radius from which to delete nodes and the heuristic length.
 // NOTE: degree**radius/size should be small, i.e. if it's too big the
@@ -218,6 +549,32 @@ pub struct GreedyParams {
     // when set to true, greedy counts the degree of a node as
     // an indicator of its number of incident path
     pub use_degree: bool,
+    // when set, greedy estimates the number of incident paths by sampling
+    // paths instead of counting all of them
+    #[serde(default)]
+    pub sampling: Option<PathSampling>,
+    // ranking of the nodes to remove at each iteration
+    #[serde(default)]
+    pub scoring: Scoring,
+    // when set, greedy only ranks the nodes lying on a path of G-S within
+    // this number of edges of its current depth (the others don't reduce
+    // it), meant for GreedyDepth
+    #[serde(default)]
+    pub critical: Option<usize>,
+}
+
+// Number of chunks the samples of `count_paths_sampled` are split in, fixed
+// so the estimates don't depend on the number of threads.
+const SAMPLING_CHUNKS: usize = 64;
+
+/// Parameters of the path sampling estimator of `count_paths` (see
+/// `count_paths_sampled`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathSampling {
+    // number of paths sampled in each greedy iteration, the relative standard
+    // error of the estimates decreases as `1/sqrt(samples)`
+    pub samples: usize,
+    pub seed: [u8; 32],
 }
 
 impl GreedyParams {
@@ -243,12 +600,41 @@ impl GreedyParams {
 }
 
 // greedy_reduce implements the Algorithm 5 of https://eprint.iacr.org/2018/944.pdf
-fn greedy_reduce(g: &mut Graph, d: AttackAlgo) -> ExclusionSet {
+fn greedy_reduce<I: NodeIndex>(g: &Graph<I>, d: AttackAlgo) -> Result<ExclusionSet> {
+    let scorer = match &d {
+        AttackAlgo::GreedyDepth(_, p) | AttackAlgo::GreedySize(_, p) => p.scoring.scorer(),
+        _ => return Err(Error::InvalidAttack(Box::new(d))),
+    };
+    greedy_reduce_with(g, d, scorer.as_ref())
+}
+
+/// Greedy attack ranking the nodes with `scorer` instead of the one selected
+/// by `GreedyParams::scoring`, to compare different scorers in the same loop.
+pub fn greedy_reduce_with<I: NodeIndex>(
+    g: &Graph<I>,
+    d: AttackAlgo,
+    scorer: &dyn NodeScorer<I>,
+) -> Result<ExclusionSet> {
+    greedy_reduce_curve(g, d, scorer, None)
+}
+
+fn greedy_reduce_curve<I: NodeIndex>(
+    g: &Graph<I>,
+    d: AttackAlgo,
+    scorer: &dyn NodeScorer<I>,
+    curve: Option<&mut Curve>,
+) -> Result<ExclusionSet> {
     match d {
         AttackAlgo::GreedyDepth(depth, p) => {
-            greedy_reduce_main(g, p, &|set: &ExclusionSet, g: &mut Graph| {
-                g.depth_exclude(set) > depth
-            })
+            let s = ExclusionSet::new(g);
+            greedy_reduce_main(
+                g,
+                s,
+                p,
+                scorer,
+                &|set: &ExclusionSet, g: &Graph<I>| g.depth_exclude(set) > depth,
+                curve,
+            )
         }
         AttackAlgo::GreedySize(size, p) => {
             // FIXME: To hit exactly the `target_size` we should consider the number of nodes
@@ -258,58 +644,155 @@ fn greedy_reduce(g: &mut Graph, d: AttackAlgo) -> ExclusionSet {
             let mut p = p.clone();
             p.k = std::cmp::min(p.k, (size as f32 * 0.01).ceil() as usize);
 
-            greedy_reduce_main(g, p, &|set: &ExclusionSet, _: &mut Graph| set.size() < size)
+            let s = ExclusionSet::new(g);
+            greedy_reduce_main(
+                g,
+                s,
+                p,
+                scorer,
+                &|set: &ExclusionSet, _: &Graph<I>| set.size() < size,
+                curve,
+            )
         }
-        _ => panic!("invalid AttackAlgo option"),
+        _ => Err(Error::InvalidAttack(Box::new(d))),
     }
 }
 
-fn greedy_reduce_main(
-    g: &mut Graph,
+/// Extend `s` with the Greedy attack, ranking the nodes with `scorer`, until
+/// `f` is false, recording each iteration in `curve` if given.
+pub(crate) fn greedy_reduce_main<I: NodeIndex>(
+    g: &Graph<I>,
+    mut s: ExclusionSet,
     p: GreedyParams,
-    f: &dyn Fn(&ExclusionSet, &mut Graph) -> bool,
-) -> ExclusionSet {
-    let mut s = ExclusionSet::new(g);
-    g.children_project();
-    let mut inradius: NodeSet = NodeSet::default();
+    scorer: &dyn NodeScorer<I>,
+    f: &dyn Fn(&ExclusionSet, &Graph<I>) -> bool,
+    curve: Option<&mut Curve>,
+) -> Result<ExclusionSet> {
+    let mut recorder = CurveRecorder::new(curve);
+    let mut inradius = StampedNodeSet::new(g.size());
+    let mut iteration = 0;
+    recorder.record(g, &s, iteration);
     while f(&s, g) {
         // TODO use p.length when more confidence in the trick
-        let incidents = count_paths(g, &s, &p);
-        append_removal(g, &mut s, &mut inradius, &incidents, &p);
+        let incidents = match p.critical {
+            Some(slack) => scorer.rank_candidates(g, &s, &p, &critical_candidates(g, &s, slack)),
+            None => scorer.rank(g, &s, &p),
+        };
+        append_removal(g, &mut s, &mut inradius, &incidents, &p)?;
         debug!(
             "\t-> greedy step: exclusion set len {}, inradius len {}",
             s.size(),
             inradius.len()
         );
-        // TODO
-        // 1. Find what should be the normal behavior: clearing or continue
+        // TODO Find what should be the normal behavior: clearing or continue
         // updating the inradius set
-        // 2. In the latter case, optimization to not re-allocate each time
-        // since could be quite big with large k and radius
         if p.reset {
             inradius.clear();
         }
+        iteration += 1;
+        recorder.record(g, &s, iteration);
+    }
+    Ok(s)
+}
+
+// Records the points of `curve` (if any), timing the attack without the
+// depth passes it takes to measure them.
+struct CurveRecorder<'a> {
+    curve: Option<&'a mut Curve>,
+    start: Instant,
+    // time spent measuring the points so far
+    measuring: Duration,
+}
+
+impl<'a> CurveRecorder<'a> {
+    fn new(curve: Option<&'a mut Curve>) -> Self {
+        CurveRecorder {
+            curve,
+            start: Instant::now(),
+            measuring: Duration::default(),
+        }
     }
-    s
+
+    // Add the point of `s` after `iteration` steps.
+    fn record<I: NodeIndex>(&mut self, g: &Graph<I>, s: &ExclusionSet, iteration: usize) {
+        if let Some(curve) = &mut self.curve {
+            let elapsed = self.start.elapsed() - self.measuring;
+            let measure = Instant::now();
+            let depth = g.depth_exclude(s);
+            self.measuring += measure.elapsed();
+            curve.points.push(CurvePoint {
+                iteration,
+                size: s.size(),
+                depth,
+                elapsed: elapsed.as_secs_f64(),
+            });
+        }
+    }
+}
+
+/// Same as `depth_reduce` also returning the depth/size curve of the attack,
+/// with a point per iteration (only for the Greedy and Valiant attacks that
+/// extend the set one step at a time).
+pub fn depth_reduce_with_curve<I: NodeIndex>(
+    g: &Graph<I>,
+    d: AttackAlgo,
+) -> Result<(ExclusionSet, Curve)> {
+    let mut curve = Curve::default();
+    let set = match d {
+        AttackAlgo::ValiantDepth(_) | AttackAlgo::ValiantSize(_) => {
+            valiant_reduce_curve(g, d, Some(&mut curve))?
+        }
+        AttackAlgo::GreedyDepth(_, ref p) | AttackAlgo::GreedySize(_, ref p) => {
+            let scorer = p.scoring.scorer();
+            greedy_reduce_curve(g, d, scorer.as_ref(), Some(&mut curve))?
+        }
+        _ => return Err(Error::InvalidAttack(Box::new(d))),
+    };
+    Ok((set, curve))
+}
+
+// Nodes of G-S whose longest path in G-S (forward plus backward depth) is
+// within `slack` of the depth of G-S, the only ones the scorer ranks.
+fn critical_candidates<I: NodeIndex>(g: &Graph<I>, s: &ExclusionSet, slack: usize) -> ExclusionSet {
+    let (forward, backward) = g.depths_exclude(s);
+    let depth = forward.iter().cloned().max().unwrap_or(0);
+    let mut candidates = ExclusionSet::new(g);
+    (0..g.size())
+        .filter(|&node| !s.contains(node) && forward[node] + backward[node] + slack >= depth)
+        .for_each(|node| candidates.insert(node));
+    trace!(
+        "\t-> {} critical candidates for depth {}",
+        candidates.size(),
+        depth
+    );
+    candidates
+}
+
+// Whether `node` is ranked: it's in G-S and among the `candidates` if any.
+pub(crate) fn is_ranked(s: &ExclusionSet, candidates: Option<&ExclusionSet>, node: Node) -> bool {
+    !s.contains(node) && candidates.is_none_or(|c| c.contains(node))
 }
 
 // append_removal is an adaptation of "SelectRemovalNodes" function in Algorithm 6
 // of https://eprint.iacr.org/2018/944.pdf. Instead of returning the set of nodes
 // to remove, it simply adds them to the given set.
-fn append_removal(
-    g: &Graph,
+fn append_removal<I: NodeIndex>(
+    g: &Graph<I>,
     set: &mut ExclusionSet,
-    inradius: &mut NodeSet,
+    inradius: &mut StampedNodeSet,
     incidents: &Vec<Pair>,
     params: &GreedyParams,
-) {
+) -> Result<()> {
     let radius = params.radius;
     let k = params.k;
     let iter = params.iter_topk;
+    if incidents.is_empty() {
+        return Err(Error::NoMoreNodes);
+    }
     if radius == 0 {
         // take the node with the highest number of incident path
         set.insert(incidents.iter().max_by_key(|pair| pair.1).unwrap().0);
-        return;
+        return Ok(());
     }
 
     let mut count = 0;
@@ -331,7 +814,7 @@ fn append_removal(
             break;
         }
 
-        if inradius.contains(&node.0) {
+        if inradius.contains(node.0) {
             // difference with previous insertion is that we only include
             // nodes NOT in the radius set
             excluded += 1;
@@ -378,17 +861,34 @@ fn append_removal(
         set.size(),
         (g.depth_exclude(&set) as f32) / (g.cap() as f32),
     );
+    Ok(())
 }
 
-fn compute_direct_nodes(g: &Graph, v: usize, rad: &NodeSet) -> Vec<usize> {
+fn compute_direct_nodes<I: NodeIndex>(g: &Graph<I>, v: usize, rad: &StampedNodeSet) -> Vec<usize> {
     return g.parents()[v]
         .par_iter()
         .chain(g.children()[v].par_iter())
+        .map(|node| node.node())
         .filter(|&node| !rad.contains(node))
-        .cloned()
         .collect::<Vec<usize>>();
 }
 
+// Sequential version of `compute_direct_nodes` appending the nodes to `out`.
+fn append_direct_nodes<I: NodeIndex>(
+    g: &Graph<I>,
+    v: usize,
+    rad: &StampedNodeSet,
+    out: &mut Vec<Node>,
+) {
+    out.extend(
+        g.parents()[v]
+            .iter()
+            .chain(g.children()[v].iter())
+            .map(|node| node.node())
+            .filter(|&node| !rad.contains(node)),
+    );
+}
+
 /// update_radius_set fills the given inradius set with nodes that inside a radius
 /// of the given node. Size of the radius is given radius. It corresponds to the
 /// under-specified function "UpdateNodesInRadius" in algo. 6 of
@@ -399,7 +899,12 @@ fn compute_direct_nodes(g: &Graph, v: usize, rad: &NodeSet) -> Vec<usize> {
 /// again because we assume we already found all its closest nodes within a
 /// specified `radius` (if the `radius` increased across calls we would be missing
 /// nodes that were farther away in comparison to earlier calls).
-pub fn update_radius_set(g: &Graph, node: usize, inradius: &mut NodeSet, p: &GreedyParams) {
+pub fn update_radius_set<I: NodeIndex>(
+    g: &Graph<I>,
+    node: usize,
+    inradius: &mut StampedNodeSet,
+    p: &GreedyParams,
+) {
     let radius = p.radius;
     let mut closests: Vec<Node> = Vec::with_capacity(radius * 10);
     // FIXME: We should be able to better estimate the size of this scratch
@@ -437,9 +942,7 @@ pub fn update_radius_set(g: &Graph, node: usize, inradius: &mut NodeSet, p: &Gre
             closests.clear();
             // grab all direct nodes of those already in radius "i"
             for &v in tosearch.iter() {
-                compute_direct_nodes(g, v, inradius)
-                    .into_iter()
-                    .for_each(|x| closests.push(x));
+                append_direct_nodes(g, v, inradius, &mut closests);
             }
             closests
         };
@@ -459,7 +962,7 @@ pub fn update_radius_set(g: &Graph, node: usize, inradius: &mut NodeSet, p: &Gre
 }
 
 #[derive(Clone, Debug, Eq)]
-pub struct Pair(usize, usize);
+pub struct Pair(pub usize, pub usize);
 
 impl Ord for Pair {
     fn cmp(&self, other: &Self) -> Ordering {
@@ -484,9 +987,23 @@ impl PartialEq for Pair {
 ///      Index is the the index of the node, value is the paths count.
 /// 2. the top k nodes indexes that have the higest incident paths
 ///      The number of incident path is not given.
-pub fn count_paths(g: &Graph, s: &ExclusionSet, p: &GreedyParams) -> Vec<Pair> {
+pub fn count_paths<I: NodeIndex>(g: &Graph<I>, s: &ExclusionSet, p: &GreedyParams) -> Vec<Pair> {
+    count_paths_among(g, s, p, None)
+}
+
+// Same as `count_paths` only returning the `candidates` (if any), the paths
+// are still counted in all of G-S but the other nodes aren't scored.
+pub(crate) fn count_paths_among<I: NodeIndex>(
+    g: &Graph<I>,
+    s: &ExclusionSet,
+    p: &GreedyParams,
+    candidates: Option<&ExclusionSet>,
+) -> Vec<Pair> {
     if p.use_degree {
-        return count_paths_degree(g, s);
+        return count_paths_degree_among(g, s, candidates);
+    }
+    if let Some(sampling) = &p.sampling {
+        return count_paths_sampled_among(g, s, p.length, sampling, candidates);
     }
     let length = p.length;
     // dimensions are [n][depth]
@@ -509,12 +1026,13 @@ pub fn count_paths(g: &Graph, s: &ExclusionSet, p: &GreedyParams) -> Vec<Pair> {
             // checking each parents (vs only checking direct + 1parent in C#)
             // no ending path for node i if the parent is contained in S
             // since G - S doesn't have this parent
-            if !s.contains(e.parent) {
-                ending_paths[e.child][d] += ending_paths[e.parent][d - 1];
+            let (parent, child) = (e.parent.node(), e.child.node());
+            if !s.contains(parent) {
+                ending_paths[child][d] += ending_paths[parent][d - 1];
 
                 // difference vs the pseudo code: like in C#, increase parent count
                 // instead of iterating over children of node i
-                starting_paths[e.parent][d] += starting_paths[e.child][d - 1];
+                starting_paths[parent][d] += starting_paths[child][d - 1];
             }
         });
     }
@@ -537,7 +1055,7 @@ pub fn count_paths(g: &Graph, s: &ExclusionSet, p: &GreedyParams) -> Vec<Pair> {
     let mut incidents = if p.parallel {
         (0..g.size())
             .into_par_iter()
-            .filter(|&n| !s.contains(n))
+            .filter(|&n| is_ranked(s, candidates, n))
             .fold(
                 || Vec::new(),
                 |mut acc, n| {
@@ -553,13 +1071,13 @@ pub fn count_paths(g: &Graph, s: &ExclusionSet, p: &GreedyParams) -> Vec<Pair> {
                 },
             )
     } else {
-        (0..g.size()).into_iter().filter(|&n| !s.contains(n)).fold(
-            Vec::with_capacity(g.size()),
-            |mut acc, n| {
+        (0..g.size())
+            .into_iter()
+            .filter(|&n| is_ranked(s, candidates, n))
+            .fold(Vec::with_capacity(g.size()), |mut acc, n| {
                 acc.push(incident_of(n));
                 acc
-            },
-        )
+            })
     };
 
     if p.parallel {
@@ -571,19 +1089,169 @@ pub fn count_paths(g: &Graph, s: &ExclusionSet, p: &GreedyParams) -> Vec<Pair> {
     incidents
 }
 
-fn count_paths_degree(g: &Graph, s: &ExclusionSet) -> Vec<Pair> {
+/// Estimate the number of incident paths of `length` edges of each node of
+/// G-S (the same ranking `count_paths` returns) in `O(samples * length)`
+/// instead of `O(length * edges)`: each sample walks back `length` steps from
+/// a random node through random parents in G-S and weights the path by the
+/// inverse of its probability (the number of nodes times the number of
+/// parents to choose from at each step), so the sum of the weights of the
+/// paths through each node is an unbiased estimate of its incidence.
+pub fn count_paths_sampled<I: NodeIndex>(
+    g: &Graph<I>,
+    s: &ExclusionSet,
+    length: usize,
+    sampling: &PathSampling,
+) -> Vec<Pair> {
+    count_paths_sampled_among(g, s, length, sampling, None)
+}
+
+fn count_paths_sampled_among<I: NodeIndex>(
+    g: &Graph<I>,
+    s: &ExclusionSet,
+    length: usize,
+    sampling: &PathSampling,
+    candidates: Option<&ExclusionSet>,
+) -> Vec<Pair> {
+    // different samples in each greedy iteration
+    let mut d = blake3::Hasher::new();
+    d.update(&sampling.seed);
+    d.update(&s.size().to_be_bytes()[..]);
+    let seed = d.finalize();
+
+    // the samples are split in a fixed number of chunks (independent of the
+    // threads available) each with its own RNG, and their paths added up in
+    // order to a single accumulator, so the estimates only depend on the seed
+    let chunks = SAMPLING_CHUNKS;
+    let sampled: Vec<(Vec<Node>, Vec<f64>)> = (0..chunks)
+        .into_par_iter()
+        .map(|chunk| {
+            let mut d = blake3::Hasher::new();
+            d.update(seed.as_bytes());
+            d.update(&chunk.to_be_bytes()[..]);
+            let mut local_seed: [u8; 32] = [0; 32];
+            local_seed.copy_from_slice(d.finalize().as_bytes());
+            let mut rng = ChaCha20Rng::from_seed(local_seed);
+
+            // nodes of the complete paths (`length + 1` each) and their weights
+            let mut paths = Vec::new();
+            let mut weights = Vec::new();
+            let mut path = Vec::with_capacity(length + 1);
+            let mut alive = Vec::new();
+            let samples = sampling.samples / chunks + (chunk < sampling.samples % chunks) as usize;
+            for _ in 0..samples {
+                let mut node = rng.gen_range(0, g.size());
+                if s.contains(node) {
+                    continue;
+                }
+                path.clear();
+                path.push(node);
+                let mut weight = g.size() as f64;
+                while path.len() <= length {
+                    alive.clear();
+                    alive.extend(
+                        g.parents()[node]
+                            .iter()
+                            .map(|p| p.node())
+                            .filter(|&p| !s.contains(p)),
+                    );
+                    if alive.is_empty() {
+                        break;
+                    }
+                    weight *= alive.len() as f64;
+                    node = alive[rng.gen_range(0, alive.len())];
+                    path.push(node);
+                }
+                if path.len() == length + 1 {
+                    paths.extend_from_slice(&path);
+                    weights.push(weight);
+                }
+            }
+            (paths, weights)
+        })
+        .collect();
+    let mut estimates = vec![0.0; g.size()];
+    for (paths, weights) in sampled.iter() {
+        for (path, &weight) in paths.chunks(length + 1).zip(weights.iter()) {
+            path.iter().for_each(|&node| estimates[node] += weight);
+        }
+    }
+
+    let samples = std::cmp::max(sampling.samples, 1) as f64;
+    let mut incidents: Vec<Pair> = (0..g.size())
+        .filter(|&node| is_ranked(s, candidates, node))
+        .map(|node| Pair(node, (estimates[node] / samples).round() as usize))
+        .collect();
+    incidents.sort_by_key(|pair| Reverse(pair.1));
+    incidents
+}
+
+/// Accuracy of an estimated ranking of incident paths against the exact
+/// one (as returned by `count_paths`), for the top `k` nodes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidenceAccuracy {
+    pub k: usize,
+    /// Fraction of the exact top k nodes found in the estimated top k.
+    pub overlap: f64,
+    /// Mean relative error of the estimates of the exact top k nodes.
+    pub mean_relative_error: f64,
+}
+
+impl fmt::Display for IncidenceAccuracy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "top {}: overlap {:.2}%, mean relative error {:.2}%",
+            self.k,
+            self.overlap * 100.0,
+            self.mean_relative_error * 100.0
+        )
+    }
+}
+
+pub fn incidence_accuracy(exact: &[Pair], estimated: &[Pair], k: usize) -> IncidenceAccuracy {
+    let k = std::cmp::min(k, exact.len());
+    let len = exact.iter().map(|pair| pair.0 + 1).max().unwrap_or(0);
+    let mut estimates = vec![0; len];
+    estimated
+        .iter()
+        .filter(|pair| pair.0 < len)
+        .for_each(|pair| estimates[pair.0] = pair.1);
+    let top: HashSet<usize> = estimated.iter().take(k).map(|pair| pair.0).collect();
+    let found = exact
+        .iter()
+        .take(k)
+        .filter(|pair| top.contains(&pair.0))
+        .count();
+    let error: f64 = exact
+        .iter()
+        .take(k)
+        .filter(|pair| pair.1 > 0)
+        .map(|pair| (estimates[pair.0] as f64 - pair.1 as f64).abs() / pair.1 as f64)
+        .sum();
+    IncidenceAccuracy {
+        k,
+        overlap: if k == 0 { 1.0 } else { found as f64 / k as f64 },
+        mean_relative_error: if k == 0 { 0.0 } else { error / k as f64 },
+    }
+}
+
+pub(crate) fn count_paths_degree_among<I: NodeIndex>(
+    g: &Graph<I>,
+    s: &ExclusionSet,
+    candidates: Option<&ExclusionSet>,
+) -> Vec<Pair> {
     let mut v = Vec::with_capacity(g.size() - s.size());
     g.for_each_node(|&node| {
-        if s.contains(node) {
+        if !is_ranked(s, candidates, node) {
             return;
         }
         let nc = g.children()[node]
             .iter()
-            .filter(|&p| !s.contains(*p))
+            .filter(|&p| !s.contains(p.node()))
             .count();
         let np = g.parents()[node]
             .iter()
-            .filter(|&p| !s.contains(*p))
+            .filter(|&p| !s.contains(p.node()))
             .count();
         v.push(Pair(node, nc + np));
     });
@@ -595,131 +1263,238 @@ fn count_paths_degree(g: &Graph, s: &ExclusionSet) -> Vec<Pair> {
 /// For a graph G with m edges, 2^k vertices, and \delta in-ground degree,
 /// it returns a set S such that depth(G-S) <= 2^k-t
 /// It iterates over t until depth(G-S) <= depth.
-fn valiant_ab16(g: &Graph, target: usize) -> ExclusionSet {
+fn valiant_ab16<I: NodeIndex>(g: &Graph<I>, target: usize) -> Result<ExclusionSet> {
+    // G_i is G-S at iteration i: the partitions are computed once on G and
+    // only the edges left in G-S are considered
+    let partitions = valiant_partitions(g);
+    let alive = |s: &ExclusionSet, edge: &Edge<I>| {
+        !s.contains(edge.parent.node()) && !s.contains(edge.child.node())
+    };
     let mut s = ExclusionSet::new(g);
-    // FIXME can we avoid that useless first copy ?
-    let mut curr = g.remove(&s);
     loop {
-        let partitions = valiant_partitions(&curr);
+        let depth = g.depth_exclude(&s);
+        if depth <= target {
+            trace!("\t -> breaking out, depth(G-S) = {}", depth);
+            break;
+        }
+        // # of edges of G_i in each partition
+        let sizes: Vec<usize> = partitions
+            .iter()
+            .map(|partition| partition.iter().filter(|edge| alive(&s, edge)).count())
+            .collect();
         // mi = # of edges at iteration i
-        let mi = curr.count_edges();
-        let depth = curr.depth();
+        let mi: usize = sizes.iter().sum();
         // depth at iteration i
         let di = depth.next_power_of_two();
         // power of exp. such that di <= 2^ki
-        let ki = (di as f32).log2().ceil() as usize;
-        let max_size = mi / ki;
+        let ki = di.trailing_zeros() as usize;
+        // with a depth of 1 there's nothing left to halve, the partitions
+        // are removed (smallest first) until there's no edge left
+        let max_size = mi.checked_div(ki).unwrap_or(mi);
         // take the minimum partition which has a size <= mi/ki
-        let chosen: &EdgeSet = partitions
-            .iter()
-            .filter(|&partition| partition.len() > 0)
-            .filter(|&partition| partition.len() <= max_size)
-            .min_by_key(|&partition| partition.len())
-            .unwrap();
+        let chosen = (0..partitions.len())
+            .filter(|&i| sizes[i] > 0)
+            .filter(|&i| sizes[i] <= max_size)
+            .min_by_key(|&i| sizes[i])
+            .ok_or(Error::NoMorePartitions)?;
         // TODO should this be even a condition to search for the partition ?
         // Paper claims it's always the case by absurd
-        let new_depth = curr.depth_exclude_edges(chosen);
-        assert!(new_depth <= (di >> 1));
+        let new_depth = depth_exclude_partition(g, &s, chosen);
+        if ki > 0 && new_depth > (di >> 1) {
+            return Err(Error::NotHalved { depth, new_depth });
+        }
         // G_i+1 = G_i - S_i  where S_i is set of origin nodes in chosen partition
-        let mut si = ExclusionSet::new(&g);
-        chosen.iter().for_each(|edge| si.insert(edge.parent));
+        let mut si = ExclusionSet::new(g);
+        partitions
+            .partition(chosen)
+            .iter()
+            .filter(|edge| alive(&s, edge))
+            .for_each(|edge| si.insert(edge.parent.node()));
         trace!(
-        "m/k = {}/{} = {}, chosen = {:?}, new_depth {}, curr.depth() {}, curr.dpeth_exclude {}, new edges {}, si {:?}",
-        mi,
-        ki,
-        max_size,
-        chosen,
-        new_depth,
-        curr.depth(),
-        curr.depth_exclude(&si),
-        curr.count_edges(),
-        si,
+            "m/k = {}/{} = {}, chosen = {} ({} edges), new_depth {}, depth {}, si {:?}",
+            mi,
+            ki,
+            max_size,
+            chosen,
+            sizes[chosen],
+            new_depth,
+            depth,
+            si,
         );
-        curr = curr.remove(&si);
         s.extend(&si);
+    }
+    Ok(s)
+}
 
-        if curr.depth() <= target {
-            trace!("\t -> breaking out, depth(G-S) = {}", g.depth_exclude(&s));
-            break;
+// Depth of G-S without the edges of the partition of msbd `bit`.
+fn depth_exclude_partition<I: NodeIndex>(g: &Graph<I>, s: &ExclusionSet, bit: usize) -> usize {
+    let mut depths = vec![0; g.size()];
+    for (node, parents) in g.parents().iter().enumerate() {
+        if s.contains(node) {
+            continue;
         }
+        let child = I::from_node(node);
+        depths[node] = parents
+            .iter()
+            .filter(|&&p| !s.contains(p.node()) && utils::msbd(&Edge::new(p, child)) != bit)
+            .map(|p| depths[p.node()] + 1)
+            .max()
+            .unwrap_or(0);
+    }
+    depths.into_iter().max().unwrap_or(0)
+}
+
+fn valiant_reduce<I: NodeIndex>(g: &Graph<I>, d: AttackAlgo) -> Result<ExclusionSet> {
+    match d {
+        AttackAlgo::ValiantAB16(depth) => valiant_ab16(g, depth),
+        _ => valiant_reduce_curve(g, d, None),
     }
-    return s;
 }
 
-fn valiant_reduce(g: &Graph, d: AttackAlgo) -> ExclusionSet {
+fn valiant_reduce_curve<I: NodeIndex>(
+    g: &Graph<I>,
+    d: AttackAlgo,
+    curve: Option<&mut Curve>,
+) -> Result<ExclusionSet> {
     match d {
         // valiant_reduce returns a set S such that depth(G - S) < target.
         // It implements the algo 8 in the https://eprint.iacr.org/2018/944.pdf paper.
         AttackAlgo::ValiantDepth(depth) => {
-            valiant_reduce_main(g, &|set: &ExclusionSet| g.depth_exclude(set) > depth)
+            valiant_reduce_main(g, &|set: &ExclusionSet| g.depth_exclude(set) > depth, curve)
         }
         AttackAlgo::ValiantSize(size) => {
-            valiant_reduce_main(g, &|set: &ExclusionSet| set.size() < size)
+            valiant_reduce_main(g, &|set: &ExclusionSet| set.size() < size, curve)
         }
-        AttackAlgo::ValiantAB16(depth) => valiant_ab16(g, depth),
-        _ => panic!("that should not happen"),
+        _ => Err(Error::InvalidAttack(Box::new(d))),
     }
 }
 
-fn valiant_reduce_main(g: &Graph, f: &dyn Fn(&ExclusionSet) -> bool) -> ExclusionSet {
+fn valiant_reduce_main<I: NodeIndex>(
+    g: &Graph<I>,
+    f: &dyn Fn(&ExclusionSet) -> bool,
+    curve: Option<&mut Curve>,
+) -> Result<ExclusionSet> {
+    let mut recorder = CurveRecorder::new(curve);
     let partitions = valiant_partitions(g);
-    // TODO replace by a simple bitset or boolean vec
-    let mut chosen: Vec<usize> = Vec::new();
+    let mut chosen = vec![false; partitions.len()];
     let mut s = ExclusionSet::new(g);
     // returns the smallest next partition unchosen
     // mut is required because it changes chosen which is mut
-    let mut find_next = || -> &EdgeSet {
+    let mut find_next = || -> Result<&[Edge<I>]> {
         match partitions
             .iter()
             .enumerate()
             // only take partitions with edges in it
-            .filter(|&(_, values)| values.len() > 0)
+            .filter(|&(_, values)| !values.is_empty())
             // only take the ones we didn't choose before
-            .filter(|&(i, _)| !chosen.contains(&i))
+            .filter(|&(i, _)| !chosen[i])
             // take the smallest one
             .min_by_key(|&(_, values)| values.len())
         {
             Some((i, val)) => {
-                chosen.push(i);
-                val
+                chosen[i] = true;
+                Ok(val)
             }
-            None => panic!("no more partitions to use"),
+            None => Err(Error::NoMorePartitions),
         }
     };
+    let mut iteration = 0;
+    recorder.record(g, &s, iteration);
     while f(&s) {
-        let partition = find_next();
+        let partition = find_next()?;
         // add the origin node for each edges in the chosen partition
-        partition.iter().for_each(|edge| s.insert(edge.parent));
+        partition
+            .iter()
+            .for_each(|edge| s.insert(edge.parent.node()));
+        iteration += 1;
+        recorder.record(g, &s, iteration);
     }
 
-    return s;
+    Ok(s)
+}
+
+/// Edges of a graph grouped by the most significant bit in which their
+/// nodes differ (see `valiant_partitions`), stored contiguously: partition
+/// `i` is `edges[offsets[i]..offsets[i + 1]]`.
+pub(crate) struct ValiantPartitions<I = Node> {
+    edges: Vec<Edge<I>>,
+    offsets: Vec<usize>,
+}
+
+impl<I: NodeIndex> ValiantPartitions<I> {
+    /// Number of partitions, one per bit of a node index.
+    pub(crate) fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    pub(crate) fn partition(&self, i: usize) -> &[Edge<I>] {
+        &self.edges[self.offsets[i]..self.offsets[i + 1]]
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &[Edge<I>]> + '_ {
+        (0..self.len()).map(move |i| self.partition(i))
+    }
 }
 
 // valiant_partitions returns the sets E_i and S_i from the given graph
 // according to the definition algorithm 8 from
 // https://eprint.iacr.org/2018/944.pdf .
-fn valiant_partitions(g: &Graph) -> Vec<EdgeSet> {
-    let bs = utils::node_bitsize();
-    let mut eis = Vec::with_capacity(bs);
-    for _ in 0..bs {
-        eis.push(EdgeSet::default());
-    }
+// The edges are counted per msbd in parallel and then placed in a single
+// array sorted by msbd (a counting sort), keeping the order of `for_each_edge`
+// inside each partition.
+pub(crate) fn valiant_partitions<I: NodeIndex>(g: &Graph<I>) -> ValiantPartitions<I> {
+    let bs = utils::node_bitsize::<I>();
+    let counts = g
+        .parents()
+        .par_iter()
+        .enumerate()
+        .fold(
+            || vec![0; bs],
+            |mut acc, (child, parents)| {
+                let child = I::from_node(child);
+                for &parent in parents.iter() {
+                    acc[utils::msbd(&Edge::new(parent, child))] += 1;
+                }
+                acc
+            },
+        )
+        .reduce(
+            || vec![0; bs],
+            |mut acc, counts| {
+                acc.iter_mut().zip(counts).for_each(|(a, c)| *a += c);
+                acc
+            },
+        );
 
+    let mut offsets = Vec::with_capacity(bs + 1);
+    offsets.push(0);
+    for count in counts.into_iter() {
+        offsets.push(offsets.last().unwrap() + count);
+    }
+    let mut next = offsets.clone();
+    let mut edges = vec![
+        Edge {
+            parent: I::default(),
+            child: I::default()
+        };
+        offsets[bs]
+    ];
     g.for_each_edge(|edge| {
         let bit = utils::msbd(edge);
         debug_assert!(bit < bs);
         // edge j -> i differs at the nth bit
-        eis[bit].insert(edge.clone());
+        edges[next[bit]] = edge.clone();
+        next[bit] += 1;
     });
 
-    eis
+    ValiantPartitions { edges, offsets }
 }
 
 #[cfg(test)]
 mod test {
     use super::super::graph;
     use super::*;
-    use crate::graph::{DRGAlgo, Edge};
+    use crate::graph::{DRGAlgo, NodeSet};
     use rand::Rng;
 
     use std::collections::HashSet;
@@ -754,15 +1529,14 @@ mod test {
 
     #[test]
     fn test_greedy() {
-        let mut graph = graph::tests::graph_from(GREEDY_PARENTS.to_vec());
-        graph.children_project();
+        let graph = graph::tests::graph_from(GREEDY_PARENTS.to_vec());
         let params = GreedyParams {
             k: 1,
             radius: 0,
             length: 2,
             ..GreedyParams::default()
         };
-        let s = greedy_reduce(&mut graph, AttackAlgo::GreedyDepth(2, params));
+        let s = greedy_reduce(&graph, AttackAlgo::GreedyDepth(2, params)).unwrap();
         assert_eq!(s, ExclusionSet::from_nodes(&graph, vec![3, 4]));
         let params = GreedyParams {
             k: 1,
@@ -771,7 +1545,7 @@ mod test {
             reset: true,
             ..GreedyParams::default()
         };
-        let s = greedy_reduce(&mut graph, AttackAlgo::GreedyDepth(2, params));
+        let s = greedy_reduce(&graph, AttackAlgo::GreedyDepth(2, params)).unwrap();
         // + incidents [Pair(2, 7), Pair(4, 7), Pair(3, 6), Pair(0, 5), Pair(1, 5), Pair(5, 3)]
         //         -> iteration 1 : node 2 inserted -> inradius {0, 3, 1, 2, 4}
         //         -> added 1/6 nodes in |S| = 1, depth(G-S) = 4 = 0.667n
@@ -788,7 +1562,7 @@ mod test {
             length: 2,
             ..GreedyParams::default()
         };
-        let s = greedy_reduce(&mut graph, AttackAlgo::GreedyDepth(2, params));
+        let s = greedy_reduce(&graph, AttackAlgo::GreedyDepth(2, params)).unwrap();
         // iteration 1: incidents [Pair(2, 7), Pair(4, 7), Pair(3, 6), Pair(0, 5), Pair(1, 5), Pair(5, 3)]
         // -> iteration 1 : node 2 inserted -> inradius {0, 3, 1, 4, 2}
         // -> added 1/1 nodes in |S| = 1, depth(G-S) = 4 = 0.667n
@@ -801,7 +1575,7 @@ mod test {
         let random_bytes = rand::thread_rng().gen::<[u8; 32]>();
         let size = (2 as usize).pow(10);
         let depth = (0.25 * size as f32) as usize;
-        let mut g3 = Graph::new(size, random_bytes, DRGAlgo::MetaBucket(3));
+        let g3 = Graph::new(size, random_bytes, DRGAlgo::MetaBucket(3)).unwrap();
         let mut params = GreedyParams {
             k: 30,
             length: 8,
@@ -810,21 +1584,73 @@ mod test {
             reset: true,
             use_degree: false,
             parallel: false,
+            sampling: None,
+            scoring: Scoring::Paths,
+            critical: None,
         };
-        let set1 = greedy_reduce(&mut g3, AttackAlgo::GreedyDepth(depth, params.clone()));
+        let set1 = greedy_reduce(&g3, AttackAlgo::GreedyDepth(depth, params.clone())).unwrap();
 
         assert!(g3.depth_exclude(&set1) < depth);
         params.use_degree = true;
-        let set2 = greedy_reduce(&mut g3, AttackAlgo::GreedyDepth(depth, params.clone()));
+        let set2 = greedy_reduce(&g3, AttackAlgo::GreedyDepth(depth, params.clone())).unwrap();
         assert!(g3.depth_exclude(&set2) < depth);
     }
 
+    #[test]
+    fn test_greedy_critical() {
+        // 0->1->2->3->4 and 5->6: only the first path is critical
+        let g = graph::tests::graph_from(vec![
+            vec![],
+            vec![0],
+            vec![1],
+            vec![2],
+            vec![3],
+            vec![],
+            vec![5],
+        ]);
+        let s = ExclusionSet::new(&g);
+        let p = GreedyParams {
+            length: 2,
+            ..GreedyParams::default()
+        };
+        let candidates = critical_candidates(&g, &s, 0);
+        assert_eq!(candidates.iter().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+        assert_eq!(critical_candidates(&g, &s, 3).size(), 7);
+        // only the candidates are ranked, with their incidence in all of G-S
+        let incidents = count_paths(&g, &s, &p);
+        let ranked = count_paths_among(&g, &s, &p, Some(&candidates));
+        assert_eq!(
+            ranked,
+            incidents
+                .into_iter()
+                .filter(|pair| candidates.contains(pair.0))
+                .collect::<Vec<_>>()
+        );
+
+        let size = 1 << 10;
+        let depth = size / 4;
+        let g = Graph::new(size, graph::tests::TEST_SEED, DRGAlgo::MetaBucket(3)).unwrap();
+        let mut params = GreedyParams {
+            k: 8,
+            radius: 2,
+            length: 8,
+            iter_topk: true,
+            reset: true,
+            ..GreedyParams::default()
+        };
+        let set1 = greedy_reduce(&g, AttackAlgo::GreedyDepth(depth, params.clone())).unwrap();
+        params.critical = Some(0);
+        let set2 = greedy_reduce(&g, AttackAlgo::GreedyDepth(depth, params.clone())).unwrap();
+        assert!(g.depth_exclude(&set2) <= depth);
+        // no removal wasted off the longest paths
+        assert!(set2.size() <= set1.size());
+    }
+
     // FIXME: Update test description with new standardize order of `topk`
     // in `count_paths`.
     #[test]
     fn test_append_removal_node() {
-        let mut graph = graph::tests::graph_from(GREEDY_PARENTS.to_vec());
-        graph.children_project();
+        let graph = graph::tests::graph_from(GREEDY_PARENTS.to_vec());
         let mut s = ExclusionSet::new(&graph);
         let mut params = GreedyParams {
             k: 3,
@@ -834,8 +1660,8 @@ mod test {
         };
         println!("graph: {:?}", graph);
         let incidents = count_paths(&graph, &s, &params);
-        let mut inradius = NodeSet::default();
-        append_removal(&graph, &mut s, &mut inradius, &incidents, &params);
+        let mut inradius = StampedNodeSet::new(graph.size());
+        append_removal(&graph, &mut s, &mut inradius, &incidents, &params).unwrap();
         // incidents: [Pair(2, 7), Pair(4, 7), Pair(3, 6), Pair(0, 5), Pair(1, 5), Pair(5, 3)]
         //  only one value since radius == 0
         assert!(s.contains(4));
@@ -843,7 +1669,7 @@ mod test {
         params.radius = 1;
         let incidents = count_paths(&graph, &s, &params);
         println!("incidents: {:?}", incidents);
-        append_removal(&graph, &mut s, &mut inradius, &incidents, &params);
+        append_removal(&graph, &mut s, &mut inradius, &incidents, &params).unwrap();
         println!("s contains: {:?}", s);
 
         // [Pair(0, 3), Pair(1, 3), Pair(2, 3), Pair(3, 3), Pair(4, 0), Pair(5, 0)]
@@ -858,34 +1684,45 @@ mod test {
 
     #[test]
     fn test_update_radius() {
-        let mut graph = graph::tests::graph_from(GREEDY_PARENTS.to_vec());
-        graph.children_project();
+        let graph = graph::tests::graph_from(GREEDY_PARENTS.to_vec());
         let node = 2;
-        let mut inradius = NodeSet::default();
+        let mut inradius = StampedNodeSet::new(graph.size());
         let mut p = GreedyParams {
             radius: 1,
             ..GreedyParams::default()
         };
 
         update_radius_set(&graph, node, &mut inradius, &p);
-        assert_eq!(inradius, HashSet::from_iter(vec![0, 1, 2, 3, 4]));
+        assert_eq!(
+            NodeSet::from_iter(inradius.iter()),
+            HashSet::from_iter(vec![0, 1, 2, 3, 4])
+        );
         p.radius = 2;
         // Start another search with a bigger `radius`, clear previous
         // `inradius` to look for the nodes all over again.
         inradius.clear();
         update_radius_set(&graph, node, &mut inradius, &p);
-        assert_eq!(inradius, HashSet::from_iter(vec![0, 1, 2, 3, 4, 5]));
+        assert_eq!(
+            NodeSet::from_iter(inradius.iter()),
+            HashSet::from_iter(vec![0, 1, 2, 3, 4, 5])
+        );
 
         // start again with parallelism
         inradius.clear();
         p.parallel = true;
         p.radius = 1;
         update_radius_set(&graph, node, &mut inradius, &p);
-        assert_eq!(inradius, HashSet::from_iter(vec![0, 1, 2, 3, 4]));
+        assert_eq!(
+            NodeSet::from_iter(inradius.iter()),
+            HashSet::from_iter(vec![0, 1, 2, 3, 4])
+        );
         inradius.clear();
         p.radius = 2;
         update_radius_set(&graph, node, &mut inradius, &p);
-        assert_eq!(inradius, HashSet::from_iter(vec![0, 1, 2, 3, 4, 5]));
+        assert_eq!(
+            NodeSet::from_iter(inradius.iter()),
+            HashSet::from_iter(vec![0, 1, 2, 3, 4, 5])
+        );
     }
 
     #[test]
@@ -917,6 +1754,44 @@ mod test {
         assert_eq!(incidents, exp);
     }
 
+    #[test]
+    fn test_count_paths_sampled() {
+        let g = Graph::new(512, graph::tests::TEST_SEED, DRGAlgo::MetaBucket(3)).unwrap();
+        let mut s = ExclusionSet::new(&g);
+        (0..g.size()).step_by(16).for_each(|node| s.insert(node));
+        let mut p = GreedyParams {
+            k: 16,
+            length: 8,
+            ..GreedyParams::default()
+        };
+        let exact = count_paths(&g, &s, &p);
+        p.sampling = Some(PathSampling {
+            samples: 200_000,
+            seed: graph::tests::TEST_SEED,
+        });
+        let estimated = count_paths(&g, &s, &p);
+        assert_eq!(estimated.len(), exact.len());
+        assert!(estimated.iter().all(|pair| !s.contains(pair.0)));
+        // deterministic for the same seed and set
+        assert_eq!(estimated, count_paths(&g, &s, &p));
+        // whatever the number of threads
+        for threads in &[1, 3] {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(*threads)
+                .build()
+                .unwrap();
+            assert_eq!(estimated, pool.install(|| count_paths(&g, &s, &p)));
+        }
+        // and for another copy of the same graph
+        let copy = Graph::new(512, graph::tests::TEST_SEED, DRGAlgo::MetaBucket(3)).unwrap();
+        assert_eq!(estimated, count_paths(&copy, &s, &p));
+
+        let accuracy = incidence_accuracy(&exact, &estimated, 32);
+        assert!(accuracy.overlap >= 0.8, "{}", accuracy);
+        assert!(accuracy.mean_relative_error < 0.1, "{}", accuracy);
+        assert_eq!(incidence_accuracy(&exact, &exact, 32).overlap, 1.0);
+    }
+
     #[test]
     fn test_count_regular_connections() {
         let seed = [1; 32];
@@ -934,7 +1809,7 @@ mod test {
                 break;
             }
 
-            let g = Graph::new(TEST_SIZE, seed, DRGAlgo::KConnector(k));
+            let g = Graph::new(TEST_SIZE, seed, DRGAlgo::KConnector(k)).unwrap();
 
             for length in 1..TEST_MAX_PATH_LENGTH {
                 // The number of incident paths for the center node should be:
@@ -968,17 +1843,31 @@ mod test {
     #[test]
     fn test_valiant_reduce_depth() {
         let graph = graph::tests::graph_from(TEST_PARENTS.to_vec());
-        let set = valiant_reduce(&graph, AttackAlgo::ValiantDepth(2));
+        let set = valiant_reduce(&graph, AttackAlgo::ValiantDepth(2)).unwrap();
         assert_eq!(set, ExclusionSet::from_nodes(&graph, vec![0, 2, 3, 4, 6]));
     }
 
     #[test]
     fn test_valiant_reduce_size() {
         let graph = graph::tests::graph_from(TEST_PARENTS.to_vec());
-        let set = valiant_reduce(&graph, AttackAlgo::ValiantSize(3));
+        let set = valiant_reduce(&graph, AttackAlgo::ValiantSize(3)).unwrap();
         assert_eq!(set, ExclusionSet::from_nodes(&graph, vec![0, 2, 3, 4, 6]));
     }
 
+    #[test]
+    fn test_invalid_attack() {
+        let graph = graph::tests::graph_from(TEST_PARENTS.to_vec());
+        let greedy = AttackAlgo::GreedyDepth(2, GreedyParams::default());
+        assert!(matches!(
+            valiant_reduce(&graph, greedy),
+            Err(Error::InvalidAttack(a)) if matches!(*a, AttackAlgo::GreedyDepth(2, _))
+        ));
+        assert!(matches!(
+            greedy_reduce(&graph, AttackAlgo::ValiantDepth(2)),
+            Err(Error::InvalidAttack(a)) if matches!(*a, AttackAlgo::ValiantDepth(2))
+        ));
+    }
+
     #[test]
     fn test_valiant_ab16() {
         let parents = vec![
@@ -994,27 +1883,45 @@ mod test {
 
         let g = graph::tests::graph_from(parents);
         let target = 4;
-        let set = valiant_reduce(&g, AttackAlgo::ValiantAB16(target));
+        let set = valiant_reduce(&g, AttackAlgo::ValiantAB16(target)).unwrap();
         assert!(g.depth_exclude(&set) < target);
         // 3->4 differs at 3rd bit and they're the only one differing at that bit
         // so set s contains origin node 3
         assert_eq!(set, ExclusionSet::from_nodes(&g, vec![3]));
+        // then {1, 5} (depth 3 -> 1) and 6, the source of the last edge
+        let set = valiant_reduce(&g, AttackAlgo::ValiantAB16(0)).unwrap();
+        assert_eq!(g.depth_exclude(&set), 0);
+        assert_eq!(set, ExclusionSet::from_nodes(&g, vec![1, 3, 5, 6]));
 
-        let g = Graph::new(TEST_SIZE, graph::tests::TEST_SEED, DRGAlgo::MetaBucket(2));
+        let g = Graph::new(TEST_SIZE, graph::tests::TEST_SEED, DRGAlgo::MetaBucket(2)).unwrap();
         let target = TEST_SIZE / 4;
-        let set = valiant_reduce(&g, AttackAlgo::ValiantAB16(target));
+        let set = valiant_reduce(&g, AttackAlgo::ValiantAB16(target)).unwrap();
         assert!(g.depth_exclude(&set) <= target);
+
+        // down to no edge at all, which may need a partition that doesn't
+        // halve the depth (reported instead of aborting)
+        match valiant_reduce(&g, AttackAlgo::ValiantAB16(0)) {
+            Ok(set) => assert_eq!(g.depth_exclude(&set), 0),
+            Err(Error::NotHalved { depth, new_depth }) => assert!(new_depth > depth / 2),
+            Err(e) => panic!("unexpected error {}", e),
+        }
+        // a target the graph already meets
+        for &target in &[g.depth(), g.depth() + 1] {
+            let set = valiant_reduce(&g, AttackAlgo::ValiantAB16(target)).unwrap();
+            assert_eq!(set.size(), 0);
+        }
     }
 
     #[test]
     fn test_valiant_partitions() {
         let graph = graph::tests::graph_from(TEST_PARENTS.to_vec());
-        let edges = valiant_partitions(&graph);
-        assert_eq!(edges.len(), utils::node_bitsize());
-        edges
-            .into_iter()
+        let partitions = valiant_partitions(&graph);
+        assert_eq!(partitions.len(), utils::node_bitsize::<Node>());
+        partitions
+            .iter()
+            .map(|edges| HashSet::from_iter(edges.iter().cloned()))
             .enumerate()
-            .for_each(|(i, edges)| match i {
+            .for_each(|(i, edges): (usize, HashSet<Edge>)| match i {
                 0 => {
                     assert_eq!(
                         edges,
@@ -1047,6 +1954,259 @@ mod test {
             });
     }
 
+    #[test]
+    fn test_node_index() {
+        // the attacks find the same sets on a graph stored with u32 nodes
+        let spec = GraphSpec {
+            size: 256,
+            seed: graph::tests::TEST_SEED,
+            algo: DRGAlgo::MetaBucket(3),
+        };
+        let g = Graph::new_from_rng(spec, &mut ChaCha20Rng::from_seed(spec.seed)).unwrap();
+        let g32 = Graph::<u32>::generate(spec, &mut ChaCha20Rng::from_seed(spec.seed)).unwrap();
+        let depth = spec.size / 4;
+        let greedy = GreedyParams {
+            k: 8,
+            radius: 2,
+            length: 8,
+            reset: true,
+            iter_topk: true,
+            ..GreedyParams::default()
+        };
+        for attack in vec![
+            AttackAlgo::ValiantDepth(depth),
+            AttackAlgo::ValiantAB16(depth),
+            AttackAlgo::GreedyDepth(depth, greedy.clone()),
+            AttackAlgo::GreedyDepth(
+                depth,
+                GreedyParams {
+                    scoring: Scoring::Betweenness { slack: 2 },
+                    ..greedy
+                },
+            ),
+        ] {
+            let s = depth_reduce(&g, attack.clone()).unwrap();
+            assert_eq!(depth_reduce(&g32, attack).unwrap(), s);
+        }
+        assert_eq!(valiant_partitions(&g32).len(), 32);
+    }
+
+    #[test]
+    fn test_concurrent_attacks() {
+        // attacks share the graph (and its lazily projected children)
+        let g = Graph::new(TEST_SIZE, graph::tests::TEST_SEED, DRGAlgo::MetaBucket(3)).unwrap();
+        let depth = TEST_SIZE / 4;
+        let params = GreedyParams {
+            k: 4,
+            radius: 2,
+            length: 8,
+            ..GreedyParams::default()
+        };
+        let attacks = vec![
+            AttackAlgo::GreedyDepth(depth, params.clone()),
+            AttackAlgo::GreedySize(depth, params),
+            AttackAlgo::ValiantDepth(depth),
+        ];
+        let concurrent: Vec<ExclusionSet> = attacks
+            .par_iter()
+            .map(|attack| depth_reduce(&g, attack.clone()).unwrap())
+            .collect();
+        for (attack, s) in attacks.into_iter().zip(concurrent) {
+            assert_eq!(depth_reduce(&g, attack).unwrap(), s);
+        }
+    }
+
+    #[test]
+    fn test_attack_with_profile_concurrency() {
+        let spec = GraphSpec {
+            size: 256,
+            seed: graph::tests::TEST_SEED,
+            algo: DRGAlgo::MetaBucket(3),
+        };
+        let params = GreedyParams {
+            k: 4,
+            radius: 2,
+            length: 8,
+            ..GreedyParams::default()
+        };
+        let profile = |concurrency| AttackProfile {
+            runs: 3,
+            range: TargetRange {
+                start: 0.2,
+                interval: 0.1,
+                end: 0.5,
+            },
+            attack: AttackAlgo::GreedyDepth(0, params.clone()),
+            concurrency,
+            warm_start: false,
+            curve: false,
+            index: IndexWidth::Usize,
+        };
+        let sequential = attack_with_profile(spec, &profile(1)).unwrap();
+        for &concurrency in &[2, 7] {
+            let concurrent = attack_with_profile(spec, &profile(concurrency)).unwrap();
+            assert_eq!(concurrent.results.len(), sequential.results.len());
+            for (c, s) in concurrent.results.iter().zip(sequential.results.iter()) {
+                assert_eq!(c.target, s.target);
+                assert_eq!(c.mean_depth, s.mean_depth);
+                assert_eq!(c.mean_size, s.mean_size);
+            }
+        }
+        assert!(matches!(
+            attack_with_profile(spec, &profile(0)),
+            Err(Error::InvalidParams(_))
+        ));
+
+        // the graphs stored as u32 give the same results
+        for &index in &[IndexWidth::Auto, IndexWidth::U32] {
+            let compact = AttackProfile {
+                index,
+                ..profile(2)
+            };
+            let compact = attack_with_profile(spec, &compact).unwrap();
+            for (c, s) in compact.results.iter().zip(sequential.results.iter()) {
+                assert_eq!(c.mean_depth, s.mean_depth);
+                assert_eq!(c.mean_size, s.mean_size);
+            }
+        }
+
+        // the warm-started Greedy finds the same sets for depth targets
+        let warm = AttackProfile {
+            warm_start: true,
+            ..profile(2)
+        };
+        let warm = attack_with_profile(spec, &warm).unwrap();
+        for (w, s) in warm.results.iter().zip(sequential.results.iter()) {
+            assert_eq!(w.mean_depth, s.mean_depth);
+            assert_eq!(w.mean_size, s.mean_size);
+        }
+        assert!(sequential.curves.is_empty());
+
+        // a curve per run, going as far as the smallest depth target, which
+        // is the same with and without warm start
+        let mut curves = Vec::new();
+        for &warm_start in &[false, true] {
+            let profile = AttackProfile {
+                warm_start,
+                curve: true,
+                ..profile(2)
+            };
+            let results = attack_with_profile(spec, &profile).unwrap();
+            assert_eq!(results.curves.len(), profile.runs);
+            let mut csv = Vec::new();
+            results.curves_to_csv(&mut csv).unwrap();
+            let csv = String::from_utf8(csv).unwrap();
+            assert!(csv.starts_with("run,iteration,size,depth,elapsed\n0,0,0,"));
+            for curve in results.curves.iter() {
+                let last = curve.points.last().unwrap();
+                assert!(last.depth as f64 <= 0.2 * spec.size as f64);
+            }
+            curves.push(results.curves);
+        }
+        for (cold, warm) in curves[0].iter().zip(curves[1].iter()) {
+            let points = |curve: &Curve| -> Vec<(usize, usize)> {
+                curve.points.iter().map(|p| (p.size, p.depth)).collect()
+            };
+            assert_eq!(points(cold), points(warm));
+        }
+        let profile = AttackProfile {
+            attack: AttackAlgo::ValiantAB16(0),
+            curve: true,
+            ..profile(1)
+        };
+        assert!(matches!(
+            attack_with_profile(spec, &profile),
+            Err(Error::InvalidAttack(_))
+        ));
+    }
+
+    #[test]
+    fn test_attack_trajectory() {
+        let g = Graph::new(TEST_SIZE, graph::tests::TEST_SEED, DRGAlgo::MetaBucket(3)).unwrap();
+        let params = GreedyParams {
+            k: 4,
+            radius: 2,
+            length: 8,
+            ..GreedyParams::default()
+        };
+        // unsorted on purpose
+        let depths = vec![TEST_SIZE / 4, TEST_SIZE / 2, TEST_SIZE / 8];
+        for attack in vec![
+            AttackAlgo::ValiantDepth(0),
+            AttackAlgo::ValiantSize(0),
+            AttackAlgo::GreedyDepth(0, params.clone()),
+        ] {
+            let checkpoints = attack_trajectory(&g, attack.clone(), &depths).unwrap();
+            assert_eq!(checkpoints.len(), depths.len());
+            for (checkpoint, &target) in checkpoints.iter().zip(depths.iter()) {
+                // same set as the attack on each target
+                let s = depth_reduce(&g, attack.with_target(target)).unwrap();
+                let expected = Checkpoint {
+                    target,
+                    size: s.size(),
+                    depth: g.depth_exclude(&s),
+                };
+                assert_eq!(*checkpoint, expected);
+            }
+        }
+
+        let sizes = vec![TEST_SIZE / 10, TEST_SIZE / 5];
+        let checkpoints = attack_trajectory(&g, AttackAlgo::GreedySize(0, params), &sizes).unwrap();
+        assert!(checkpoints[0].size >= sizes[0] && checkpoints[1].size >= sizes[1]);
+        assert!(checkpoints[0].size <= checkpoints[1].size);
+        assert!(checkpoints[0].depth >= checkpoints[1].depth);
+
+        assert!(matches!(
+            attack_trajectory(&g, AttackAlgo::ValiantAB16(0), &depths),
+            Err(Error::InvalidAttack(_))
+        ));
+    }
+
+    #[test]
+    fn test_depth_reduce_with_curve() {
+        let g = Graph::new(TEST_SIZE, graph::tests::TEST_SEED, DRGAlgo::MetaBucket(3)).unwrap();
+        let depth = TEST_SIZE / 4;
+        let params = GreedyParams {
+            k: 4,
+            radius: 2,
+            length: 8,
+            ..GreedyParams::default()
+        };
+        for attack in vec![
+            AttackAlgo::ValiantDepth(depth),
+            AttackAlgo::ValiantSize(depth),
+            AttackAlgo::GreedyDepth(depth, params.clone()),
+            AttackAlgo::GreedySize(depth, params.clone()),
+        ] {
+            let (s, curve) = depth_reduce_with_curve(&g, attack.clone()).unwrap();
+            assert_eq!(s, depth_reduce(&g, attack).unwrap());
+            let first = &curve.points[0];
+            assert_eq!(
+                (first.iteration, first.size, first.depth),
+                (0, 0, g.depth())
+            );
+            let last = curve.points.last().unwrap();
+            assert_eq!((last.size, last.depth), (s.size(), g.depth_exclude(&s)));
+            for (i, pair) in curve.points.windows(2).enumerate() {
+                assert_eq!(pair[1].iteration, i + 1);
+                assert!(pair[0].size <= pair[1].size);
+                assert!(pair[0].elapsed <= pair[1].elapsed);
+            }
+        }
+
+        let (_, curve) = depth_reduce_with_curve(&g, AttackAlgo::ValiantDepth(depth)).unwrap();
+        let mut csv = Vec::new();
+        curve.to_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.starts_with("iteration,size,depth,elapsed\n0,0,"));
+        assert_eq!(csv.lines().count(), curve.points.len() + 1);
+
+        assert!(matches!(
+            depth_reduce_with_curve(&g, AttackAlgo::ValiantAB16(depth)),
+            Err(Error::InvalidAttack(_))
+        ));
+    }
+
     #[test]
     fn greedy_k_ratio() {
         let size = 20; // n = 2^20
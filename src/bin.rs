@@ -1,13 +1,25 @@
 #![deny(warnings)]
+use drg::annealing::{AnnealingParams, InitialSet};
 use drg::attacks::{
-    attack, attack_with_profile, AttackAlgo, AttackProfile, GreedyParams, TargetRange,
+    attack, attack_with_profile, depth_reduce, AttackAlgo, AttackProfile, GreedyParams,
+    TargetRange,
 };
-use drg::graph::{DRGAlgo, Graph, GraphSpec};
+use drg::conformance::{check_sampler, drsample_pmf, ren21_pmf, BucketBound};
+use drg::evolution::EvolutionParams;
+use drg::export::Exporter;
+use drg::graph::{
+    DRGAlgo, ExclusionSet, Graph, GraphSpec, IndexWidth, MetaBucketSampler, Ren21Sampler,
+};
+use drg::import::{import_file, ImportFormat, Labelling};
+use drg::pebbling::{verify_schedule, PebblingGame};
+use drg::scoring::Scoring;
+use drg::stream::{generate_file, stream_depth, stream_partition_counts, ParentsReader};
 use drg::utils;
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaChaRng;
 use std::fs::File;
 use std::io::{self,Write};
+use std::path::Path;
 use std::time::Instant;
 
 use clap::{value_t_or_exit, App, Arg, ArgMatches, SubCommand};
@@ -16,8 +28,15 @@ use gperftools::profiler::PROFILER;
 
 const GRAPH_BENCH_CMD :&str = "graph-bench";
 const ATTACK_CMD :&str = "attack";
+const EXPORT_CMD :&str = "export";
+const STATS_CMD :&str = "stats";
+const CONFORMANCE_CMD :&str = "conformance";
+const PEBBLE_CMD :&str = "pebble";
+const STREAM_CMD :&str = "stream";
 const ATTACK_VALIANT :&str = "valiant";
 const ATTACK_GREEDY :&str= "greedy";
+const ATTACK_ANNEALING :&str= "annealing";
+const ATTACK_EVOLUTION :&str= "evolution";
 
 const DRG_BUCKET :&str = "bucket";
 const DRG_REN21 :&str = "ren21";
@@ -73,7 +92,7 @@ fn graph_bench(m: &ArgMatches) {
             algo: algo,
         };
         let mut rng = ChaChaRng::from_seed(specs.seed.clone());
-        Graph::new_from_rng(specs, &mut rng);
+        Graph::new_from_rng(specs, &mut rng).expect("failed to generate graph");
         avg += now.elapsed().as_millis() as f64;
     }
     let avg = (avg / (runs as f64)) as u64;
@@ -114,8 +133,8 @@ fn drg_command(m: &ArgMatches) {
         let is_depth = sub.is_present("depth");
         let is_reset = sub.is_present("noreset");
         let is_greedy_params = is_radius || is_topk || is_depth || is_reset;
-        if is_greedy_params && attack_type != "greedy" {
-            panic!("greedy attack doesn't take any --radius or --topk flag");
+        if is_greedy_params && attack_type == ATTACK_VALIANT {
+            panic!("valiant attack doesn't take any --radius or --topk flag");
         }
         if is_radius {
             s.radius = value_t_or_exit!(sub,"radius",usize);
@@ -129,6 +148,19 @@ fn drg_command(m: &ArgMatches) {
         s.reset = if is_reset { false } else { true };
         s
     };
+    // annealing starts from the greedy output
+    let annealing_params = AnnealingParams {
+        iterations: value_t_or_exit!(sub, "iterations", usize),
+        initial: InitialSet::Greedy(greedy_params.clone()),
+        seed,
+        ..AnnealingParams::default()
+    };
+    let evolution_params = EvolutionParams {
+        generations: value_t_or_exit!(sub, "generations", usize),
+        initial: InitialSet::Greedy(greedy_params.clone()),
+        seed,
+        ..EvolutionParams::default()
+    };
 
     let parse_bounds = |default:&str| -> (f64,f64) {
         let default_v = value_t_or_exit!(sub, default, f64);
@@ -167,6 +199,8 @@ fn drg_command(m: &ArgMatches) {
         match attack_type {
             ATTACK_VALIANT => (AttackAlgo::ValiantSize(set_size), range),
             ATTACK_GREEDY => (AttackAlgo::GreedySize(set_size,greedy_params), range),
+            ATTACK_ANNEALING => (AttackAlgo::AnnealingSize(set_size,annealing_params), range),
+            ATTACK_EVOLUTION => (AttackAlgo::EvolutionSize(set_size,evolution_params), range),
             _ => panic!("unknown type"),
         }
     } else {
@@ -181,6 +215,8 @@ fn drg_command(m: &ArgMatches) {
         match attack_type {
             ATTACK_VALIANT => (AttackAlgo::ValiantDepth(beta_size), range),
             ATTACK_GREEDY =>  (AttackAlgo::GreedyDepth(beta_size,greedy_params), range),
+            ATTACK_ANNEALING =>  (AttackAlgo::AnnealingDepth(beta_size,annealing_params), range),
+            ATTACK_EVOLUTION =>  (AttackAlgo::EvolutionDepth(beta_size,evolution_params), range),
             _ => panic!("unknown type"),
         }
     };
@@ -188,12 +224,21 @@ fn drg_command(m: &ArgMatches) {
         runs,
         range,
         attack,
+        concurrency: value_t_or_exit!(sub, "concurrency", usize),
+        warm_start: sub.is_present("warm"),
+        curve: sub.is_present("curve"),
+        index: match sub.value_of("index").unwrap() {
+            "auto" => IndexWidth::Auto,
+            "u32" => IndexWidth::U32,
+            "usize" => IndexWidth::Usize,
+            _ => panic!("unknown index width"),
+        },
     };
 
     println!("Running attacks on graph {}",specs);
 
     start_profile("drg");
-    let results = attack_with_profile(specs, &profile);
+    let results = attack_with_profile(specs, &profile).expect("attack failed");
     stop_profile();
     let handler : Box<dyn Write> = if sub.is_present("csv") {
         let fname = sub.value_of("csv").unwrap_or("results.csv");
@@ -202,6 +247,205 @@ fn drg_command(m: &ArgMatches) {
         Box::new(io::stdout())
     };
     results.to_csv(handler).expect("failed to write to CSV");
+
+    if let Some(fname) = sub.value_of("curve") {
+        let file = File::create(fname).expect("opening curve file failed");
+        match Path::new(fname).extension().and_then(|ext| ext.to_str()) {
+            Some("json") => results.curves_to_json(file).expect("failed to write JSON"),
+            _ => results.curves_to_csv(file).expect("failed to write CSV"),
+        }
+    }
+}
+
+fn export_command(m: &ArgMatches) {
+    let sub = m
+        .subcommand_matches(EXPORT_CMD)
+        .expect("subcommand export not recognized");
+    let pow = value_t_or_exit!(sub, "size", usize);
+    let n = 1 << pow;
+    let degree = value_t_or_exit!(sub, "degree", usize);
+    let algo = match sub.value_of("drg").unwrap()  {
+        DRG_BUCKET => DRGAlgo::MetaBucket(degree),
+        DRG_REN21 => DRGAlgo::Ren21(degree),
+        _ => panic!("DRG Algo unknown"),
+    };
+    let seed = rand::thread_rng().gen::<[u8; 32]>();
+    let g = Graph::new(n, seed, algo).expect("failed to generate graph");
+
+    // optionally run an attack to highlight its exclusion set
+    let set = match sub.value_of("attack") {
+        None => ExclusionSet::new(&g),
+        Some(attack_type) => {
+            let beta = value_t_or_exit!(sub, "beta", f64);
+            let depth = (beta * n as f64) as usize;
+            let attack = match attack_type {
+                ATTACK_VALIANT => AttackAlgo::ValiantDepth(depth),
+                ATTACK_GREEDY => AttackAlgo::GreedyDepth(depth, GreedyParams::standard(pow)),
+                _ => panic!("unknown type"),
+            };
+            depth_reduce(&g, attack).expect("attack failed")
+        }
+    };
+    let exporter = Exporter::new(&g).exclusion(&set).longest_path();
+
+    let handler : Box<dyn Write> = match sub.value_of("output") {
+        Some(fname) => Box::new(File::create(fname).expect("opening output file failed")),
+        None => Box::new(io::stdout()),
+    };
+    match sub.value_of("format").unwrap() {
+        "dot" => exporter.to_dot(handler).expect("failed to write DOT"),
+        "graphml" => exporter.to_graphml(handler).expect("failed to write GraphML"),
+        "csv" => exporter.to_csv(handler).expect("failed to write CSV"),
+        _ => panic!("unknown export format"),
+    }
+}
+
+fn stats_command(m: &ArgMatches) {
+    let sub = m
+        .subcommand_matches(STATS_CMD)
+        .expect("subcommand stats not recognized");
+    let g = match sub.value_of("input") {
+        Some(fname) => {
+            let path = Path::new(fname);
+            import_file(path, ImportFormat::from_path(path), Labelling::Topological)
+                .expect("failed to import graph")
+                .graph
+        }
+        None => {
+            let pow = value_t_or_exit!(sub, "size", usize);
+            let degree = value_t_or_exit!(sub, "degree", usize);
+            let algo = match sub.value_of("drg").unwrap()  {
+                DRG_BUCKET => DRGAlgo::MetaBucket(degree),
+                DRG_REN21 => DRGAlgo::Ren21(degree),
+                _ => panic!("DRG Algo unknown"),
+            };
+            let seed = rand::thread_rng().gen::<[u8; 32]>();
+            Graph::new(1 << pow, seed, algo).expect("failed to generate graph")
+        }
+    };
+    let stats = g.stats();
+    match sub.value_of("format").unwrap() {
+        "json" => println!(
+            "{}",
+            serde_json::to_string_pretty(&stats).expect("can't serialize to json")
+        ),
+        "table" => print!("{}", stats),
+        _ => panic!("unknown stats format"),
+    }
+}
+
+fn conformance_command(m: &ArgMatches) {
+    let sub = m
+        .subcommand_matches(CONFORMANCE_CMD)
+        .expect("subcommand conformance not recognized");
+    let pow = value_t_or_exit!(sub, "size", usize);
+    let degree = value_t_or_exit!(sub, "degree", usize);
+    let samples = value_t_or_exit!(sub, "samples", usize);
+    let drg = sub.value_of("drg").unwrap();
+    // parents sampled per node from the meta-graph of DRSample
+    let m = if drg == DRG_BUCKET { degree - 1 } else { 1 };
+    // nodes at the powers of two (where the log rounding matters), in between
+    // and with meta indexes just above them (where the f32 bound of DRSample
+    // rounds down)
+    let mut nodes: Vec<usize> = (2..=pow)
+        .flat_map(|i| vec![1 << i, 3 << (i - 1), (1 << i) / m + 1])
+        .filter(|&node| node >= 2 && node < 1 << pow)
+        .collect();
+    nodes.sort_unstable();
+    nodes.dedup();
+    // Bonferroni correction since we test all the nodes at once
+    let alpha = value_t_or_exit!(sub, "alpha", f64) / nodes.len() as f64;
+    let seed = rand::thread_rng().gen::<[u8; 32]>();
+
+    println!("Conformance of {} (degree {}) with {} samples per node", drg, degree, samples);
+    let reports = match drg {
+        DRG_BUCKET => {
+            let sampler = MetaBucketSampler { degree };
+            [BucketBound::Ceil, BucketBound::FloorPlusOne]
+                .iter()
+                .map(|&bound| {
+                    let pmf = |node| drsample_pmf(node, m, bound);
+                    (format!("{:?} bucket bound", bound), check_sampler(&sampler, pmf, &nodes, samples, alpha, seed))
+                })
+                .collect()
+        }
+        DRG_REN21 => vec![("Ren21".to_string(), check_sampler(&Ren21Sampler { degree }, ren21_pmf, &nodes, samples, alpha, seed))],
+        _ => panic!("DRG Algo unknown"),
+    };
+    for (name, report) in reports.iter() {
+        println!("Against the {} distribution:", name);
+        report.iter().for_each(|r| println!("{}", r));
+        let biased = report.iter().filter(|r| r.biased).count();
+        if biased > 0 {
+            println!("-> generator is BIASED for {}/{} nodes (alpha {:e})", biased, nodes.len(), alpha);
+        } else {
+            println!("-> generator conforms to its distribution (alpha {:e})", alpha);
+        }
+    }
+}
+
+fn pebble_command(m: &ArgMatches) {
+    let sub = m
+        .subcommand_matches(PEBBLE_CMD)
+        .expect("subcommand pebble not recognized");
+    // keep the labels of the graph so the schedule refers to the same nodes
+    let path = Path::new(sub.value_of("graph").unwrap());
+    let g = import_file(path, ImportFormat::from_path(path), Labelling::Strict)
+        .expect("failed to import graph")
+        .graph;
+    let schedule: Vec<Vec<usize>> = serde_json::from_reader(
+        File::open(sub.value_of("schedule").unwrap()).expect("can't open schedule"),
+    )
+    .expect("can't parse schedule");
+    let game = match sub.value_of("game").unwrap() {
+        "sequential" => PebblingGame::Sequential,
+        "parallel" => PebblingGame::Parallel,
+        _ => panic!("unknown pebbling game"),
+    };
+    let core_ratio = value_t_or_exit!(sub, "core-ratio", usize);
+    match verify_schedule(&g, game, &schedule) {
+        Ok(cost) => println!(
+            "legal {:?} pebbling: time = {}, space = {}, CC = {}, energy = {} (core ratio {})",
+            game, cost.time, cost.peak_memory, cost.cumulative, cost.energy(core_ratio), core_ratio
+        ),
+        Err(e) => {
+            eprintln!("illegal pebbling: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn stream_command(m: &ArgMatches) {
+    let sub = m
+        .subcommand_matches(STREAM_CMD)
+        .expect("subcommand stream not recognized");
+    let pow = value_t_or_exit!(sub, "size", usize);
+    let degree = value_t_or_exit!(sub, "degree", usize);
+    let chunk = value_t_or_exit!(sub, "chunk", usize);
+    let algo = match sub.value_of("drg").unwrap()  {
+        DRG_BUCKET => DRGAlgo::MetaBucket(degree),
+        DRG_REN21 => DRGAlgo::Ren21(degree),
+        _ => panic!("DRG Algo unknown"),
+    };
+    let spec = GraphSpec {
+        size: 1 << pow,
+        seed: rand::thread_rng().gen::<[u8; 32]>(),
+        algo,
+    };
+    let output = sub.value_of("output").unwrap();
+    let start = Instant::now();
+    generate_file(spec, chunk, output).expect("failed to generate graph");
+    println!("{} written to {} in {:?}", spec, output, start.elapsed());
+
+    let start = Instant::now();
+    let reader = ParentsReader::open(output).expect("failed to open graph");
+    let depth = stream_depth(reader, chunk, None).expect("failed to read graph");
+    println!("depth = {} ({:?})", depth, start.elapsed());
+    let reader = ParentsReader::open(output).expect("failed to open graph");
+    let counts = stream_partition_counts(reader, chunk).expect("failed to read graph");
+    for (bit, count) in counts.iter().enumerate().filter(|&(_, &c)| c > 0) {
+        println!("\tedges with msbd {:>2}: {}", bit, count);
+    }
 }
 
 fn porep_comparison() {
@@ -212,30 +456,31 @@ fn porep_comparison() {
     let deg = 6;
     let fname = format!("porep_n{}_d{}.json", n, deg);
 
-    let mut g1 = Graph::load_or_create(&fname, size, random_bytes, DRGAlgo::MetaBucket(deg));
+    let g1 = Graph::load_or_create(&fname, size, random_bytes, DRGAlgo::MetaBucket(deg))
+        .expect("failed to load graph");
     //let mut g1 = Graph::new(size, random_bytes, DRGAlgo::MetaBucket(deg));
 
     let depth = (0.25 * (size as f32)) as usize;
     println!("{}", g1.stats());
     println!("Trial #1 with target depth = 0.25n = {}", depth);
-    attack(&mut g1, AttackAlgo::ValiantDepth(depth));
+    attack(&g1, AttackAlgo::ValiantDepth(depth)).expect("attack failed");
 
     //let set_size = (0.30 * (size as f32)) as usize;
     //println!(
     //"Trial #2 with target size set = 0.30n = {} (G-S = 0.7n)",
     //set_size
     //);
-    //attack(&mut g1, AttackAlgo::ValiantSize(set_size));
+    //attack(&g1, AttackAlgo::ValiantSize(set_size));
 
     //println!(
     //"Trial #3 with Valiant AB16, target depth = 0.25n = {}",
     //depth
     //);
-    /*attack(&mut g1, AttackAlgo::ValiantAB16(depth));*/
+    /*attack(&g1, AttackAlgo::ValiantAB16(depth));*/
 
     println!("Trial #4 with Greedy DRS, target depth = 0.25n = {}", depth);
     attack(
-        &mut g1,
+        &g1,
         AttackAlgo::GreedySize(
             depth,
             GreedyParams {
@@ -247,7 +492,8 @@ fn porep_comparison() {
                 ..GreedyParams::default()
             },
         ),
-    );
+    )
+    .expect("attack failed");
 
     // Comparison with porep short paper with n = 1048576
     // graph stats: size=1048576, min parents=1, max children=26
@@ -282,7 +528,7 @@ fn greedy_attacks(n: usize) {
         algo: DRGAlgo::MetaBucket(deg),
     };
     let runs = 10;
-    //attack(&mut g1, AttackAlgo::ValiantDepth(depth));
+    //attack(&g1, AttackAlgo::ValiantDepth(depth));
 
     let greed_params = GreedyParams {
         k: 50,
@@ -295,6 +541,9 @@ fn greedy_attacks(n: usize) {
         length: 8,
         iter_topk: true,
         use_degree: false,
+        sampling: None,
+        scoring: Scoring::Paths,
+        critical: None,
         parallel: false,
     };
 
@@ -308,7 +557,7 @@ fn greedy_attacks(n: usize) {
     profile.range.interval = 0.1;
 
     start_profile("greedy");
-    let res = attack_with_profile(spec, &profile);
+    let res = attack_with_profile(spec, &profile).expect("attack failed");
     // FIXME: Turn this into a JSON output.
     println!("\n\n------------------");
     println!("Attack finished: {:?}", profile);
@@ -334,7 +583,7 @@ fn challenge_graphs() {
             "Constructing graph with seed {}",
             utils::to_hex_string(&seed)
         );
-        let g = Graph::new_from_rng(spec, &mut rng);
+        let g = Graph::new_from_rng(spec, &mut rng).expect("failed to generate graph");
         let name = format!("graph-{}.json", i);
         let file = File::create(&name).unwrap();
         serde_json::to_writer(file, &g).unwrap();
@@ -361,7 +610,7 @@ fn baseline_valiant(n: usize) {
     profile.range.end = 0.26;
     profile.range.interval = 0.05;
 
-    let res1 = attack_with_profile(spec, &profile);
+    let res1 = attack_with_profile(spec, &profile).expect("attack failed");
     // target size
     let mut profile = AttackProfile::from_attack(AttackAlgo::ValiantSize(target_size), size);
     profile.runs = 3;
@@ -369,7 +618,7 @@ fn baseline_valiant(n: usize) {
     profile.range.end = 0.31;
     profile.range.interval = 0.05;
 
-    let res2 = attack_with_profile(spec, &profile);
+    let res2 = attack_with_profile(spec, &profile).expect("attack failed");
     let json = serde_json::to_string_pretty(&vec![res1, res2]).expect("can't serialize to json");
     println!("{}", json);
 }
@@ -395,6 +644,9 @@ fn theoretical_limit() {
         length: 10,
         iter_topk: true,
         use_degree: true,
+        sampling: None,
+        scoring: Scoring::Paths,
+        critical: None,
         parallel: true,
     };
 
@@ -407,7 +659,7 @@ fn theoretical_limit() {
     profile.range.end = 0.0000116;
     profile.range.interval = 0.1;
 
-    let res0 = attack_with_profile(spec, &profile);
+    let res0 = attack_with_profile(spec, &profile).expect("attack failed");
     println!(
         "json: {}",
         serde_json::to_string_pretty(&res0).expect("can't serialize to json")
@@ -422,7 +674,7 @@ fn theoretical_limit() {
     profile.range.end = 0.04;
     profile.range.interval = 0.01;
 
-    let res1 = attack_with_profile(spec, &profile);
+    let res1 = attack_with_profile(spec, &profile).expect("attack failed");
     println!(
         "json: {}",
         serde_json::to_string_pretty(&vec![res0, res1]).expect("can't serialize to json")
@@ -449,6 +701,9 @@ fn baseline_greedy() {
         length: 10,
         iter_topk: true,
         use_degree: true,
+        sampling: None,
+        scoring: Scoring::Paths,
+        critical: None,
         parallel: true,
     };
 
@@ -461,7 +716,7 @@ fn baseline_greedy() {
     profile.range.end = 0.26;
     profile.range.interval = 0.05;
 
-    let res1 = attack_with_profile(spec, &profile);
+    let res1 = attack_with_profile(spec, &profile).expect("attack failed");
 
     let mut profile = AttackProfile::from_attack(
         AttackAlgo::GreedySize(target_depth, greed_params.clone()),
@@ -471,7 +726,7 @@ fn baseline_greedy() {
     profile.range.start = 0.15;
     profile.range.end = 0.31;
     profile.range.interval = 0.05;
-    let res2 = attack_with_profile(spec, &profile);
+    let res2 = attack_with_profile(spec, &profile).expect("attack failed");
 
     let json = serde_json::to_string_pretty(&vec![res1, res2]).expect("can't serialize to json");
     println!("{}", json);
@@ -497,6 +752,9 @@ fn baseline_large() {
         length: 10,
         iter_topk: true,
         use_degree: false,
+        sampling: None,
+        scoring: Scoring::Paths,
+        critical: None,
         parallel: false,
     };
 
@@ -509,7 +767,7 @@ fn baseline_large() {
     profile.range.end = 0.91;
     profile.range.interval = 0.10;
 
-    let res = attack_with_profile(spec, &profile);
+    let res = attack_with_profile(spec, &profile).expect("attack failed");
     println!("\n\n------------------");
     println!("Attack finished: {:?}", profile);
     let json = serde_json::to_string_pretty(&res).expect("can't serialize to json");
@@ -564,10 +822,43 @@ fn main() {
             )
             .arg(Arg::with_name("attack")
                 .long("attack")
-                .help("Type of attacks (valiant or greedy)")
+                .help("Type of attacks (valiant, greedy, annealing or evolution)")
                 .default_value(ATTACK_VALIANT)
                 .takes_value(true)
             )
+            .arg(Arg::with_name("iterations")
+                .long("iterations")
+                .help("Iteration budget of the annealing attack")
+                .default_value("1000")
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("generations")
+                .long("generations")
+                .help("Generations of the evolution attack")
+                .default_value("50")
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("concurrency")
+                .long("concurrency")
+                .help("Maximum number of (target, run) attacks running at the same time")
+                .default_value("1")
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("curve")
+                .long("curve")
+                .help("output file of the depth/size curve of each greedy or valiant run (CSV, or JSON with a .json extension)")
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("warm")
+                .long("warm")
+                .help("Run a single greedy or valiant attack per run recording all the targets")
+            )
+            .arg(Arg::with_name("index")
+                .long("index")
+                .help("Integer type to store the nodes with (auto, u32 or usize), auto picks u32 when the graph fits")
+                .default_value("auto")
+                .takes_value(true)
+            )
             .arg(Arg::with_name("size")
                 .short("n")
                 .long("size")
@@ -623,6 +914,179 @@ fn main() {
                 .help("dont reset the inradius (default true)")
             )
         )
+        .subcommand(SubCommand::with_name(EXPORT_CMD).about("Export a graph (and optionally an attack on it) for visualization")
+            .arg(Arg::with_name("size")
+                .short("n")
+                .long("size")
+                .help("Size of graph expressed as a power of 2")
+                .default_value("6")
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("drg")
+                .long("drg")
+                .help("Types of DRG graph")
+                .default_value(DRG_BUCKET)
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("degree")
+                .short("d")
+                .long("degree")
+                .help("Degree of nodes in the DRG")
+                .default_value("3")
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("format")
+                .long("format")
+                .help("Output format (dot, graphml or csv)")
+                .default_value("dot")
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .help("output file (stdout by default)")
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("attack")
+                .long("attack")
+                .help("Attack to highlight its exclusion set (valiant or greedy)")
+                .requires("beta")
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("beta")
+                .short("b")
+                .long("beta")
+                .help("Target depth of the attack expressed in percentage of the graph size")
+                .takes_value(true)
+            )
+        )
+        .subcommand(SubCommand::with_name(STATS_CMD).about("Print the statistics of a graph")
+            .arg(Arg::with_name("size")
+                .short("n")
+                .long("size")
+                .help("Size of graph expressed as a power of 2")
+                .default_value("10")
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("drg")
+                .long("drg")
+                .help("Types of DRG graph")
+                .default_value(DRG_BUCKET)
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("degree")
+                .short("d")
+                .long("degree")
+                .help("Degree of nodes in the DRG")
+                .default_value("6")
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("input")
+                .long("input")
+                .help("Import the graph from a file instead (edge list, DIMACS or JSON parents)")
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("format")
+                .long("format")
+                .help("Output format (json or table)")
+                .default_value("table")
+                .takes_value(true)
+            )
+        )
+        .subcommand(SubCommand::with_name(CONFORMANCE_CMD).about("Check the parents distribution of the DRG generators")
+            .arg(Arg::with_name("size")
+                .short("n")
+                .long("size")
+                .help("Test nodes up to this power of 2")
+                .default_value("12")
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("drg")
+                .long("drg")
+                .help("Types of DRG graph")
+                .default_value(DRG_BUCKET)
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("degree")
+                .short("d")
+                .long("degree")
+                .help("Degree of nodes in the DRG")
+                .default_value("6")
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("samples")
+                .long("samples")
+                .help("Number of parents sampled for each node")
+                .default_value("100000")
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("alpha")
+                .long("alpha")
+                .help("Significance level to flag a generator as biased")
+                .default_value("0.001")
+                .takes_value(true)
+            )
+        )
+        .subcommand(SubCommand::with_name(PEBBLE_CMD).about("Verify a pebbling schedule of a graph and report its cost")
+            .arg(Arg::with_name("graph")
+                .long("graph")
+                .help("Graph to pebble (edge list, DIMACS or JSON parents, properly labelled)")
+                .required(true)
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("schedule")
+                .long("schedule")
+                .help("JSON array with the pebbled nodes at the end of each step")
+                .required(true)
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("game")
+                .long("game")
+                .help("Pebbling game (sequential or parallel)")
+                .default_value("parallel")
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("core-ratio")
+                .long("core-ratio")
+                .help("Cost of placing a pebble relative to storing one for a step (energy complexity)")
+                .default_value("3000")
+                .takes_value(true)
+            )
+        )
+        .subcommand(SubCommand::with_name(STREAM_CMD).about("Generate a graph to disk chunk by chunk and compute its depth and Valiant partitions from it")
+            .arg(Arg::with_name("size")
+                .short("n")
+                .long("size")
+                .help("Size of graph expressed as a power of 2")
+                .default_value("20")
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("drg")
+                .long("drg")
+                .help("Types of DRG graph")
+                .default_value(DRG_BUCKET)
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("degree")
+                .short("d")
+                .long("degree")
+                .help("Degree of nodes in the DRG")
+                .default_value("6")
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("chunk")
+                .long("chunk")
+                .help("Number of nodes generated (and read) at a time")
+                .default_value("1048576")
+                .takes_value(true)
+            )
+            .arg(Arg::with_name("output")
+                .long("output")
+                .help("File to write the graph to")
+                .required(true)
+                .takes_value(true)
+            )
+        )
         .subcommand(SubCommand::with_name("greedy").about("Greedy attack"))
         .subcommand(SubCommand::with_name("challenge_graphs"))
         .subcommand(SubCommand::with_name("porep"))
@@ -655,6 +1119,16 @@ fn main() {
         drg_command(&matches);
     } else if let Some(_) = matches.subcommand_matches(GRAPH_BENCH_CMD) {
         graph_bench(&matches);
+    } else if let Some(_) = matches.subcommand_matches(EXPORT_CMD) {
+        export_command(&matches);
+    } else if let Some(_) = matches.subcommand_matches(STATS_CMD) {
+        stats_command(&matches);
+    } else if let Some(_) = matches.subcommand_matches(CONFORMANCE_CMD) {
+        conformance_command(&matches);
+    } else if let Some(_) = matches.subcommand_matches(PEBBLE_CMD) {
+        pebble_command(&matches);
+    } else if let Some(_) = matches.subcommand_matches(STREAM_CMD) {
+        stream_command(&matches);
     } else {
         eprintln!("No subcommand entered, running `porep_comparison`");
         porep_comparison();
@@ -0,0 +1,343 @@
+use rand::SeedableRng;
+use rand_chacha::ChaChaRng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::graph::{Node, ParentSampler};
+
+/// Upper bound used to select the bucket index in DRSample: the CCS
+/// [paper](https://acmccs.github.io/papers/p1001-alwenA.pdf) samples it from
+/// `[1, floor(log2(v)) + 1]` while our implementation (`sample_parent_node`)
+/// uses `[1, ceil(log2(v))]`, they only differ when `v` is a power of two
+/// (see "Logarithm floor" in the notes). Both bounds are exact here, while
+/// the sampler computes its `ceil` in `f32`, which rounds it down just above
+/// the powers of two from 2^21, so the sampler is tested against the exact
+/// distributions rather than its own rounding.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BucketBound {
+    Ceil,
+    FloorPlusOne,
+}
+
+/// Exact distribution of the distance between `node` and a parent sampled
+/// by DRSample (`meta_bucket`) with `m` parents per node (without counting
+/// the direct predecessor): `pmf[d]` is the probability of the parent being
+/// `node - d` (so `pmf[0] == 0`).
+pub fn drsample_pmf(node: Node, m: usize, bound: BucketBound) -> Vec<f64> {
+    assert!(node >= 2, "node {} has no sampled parents", node);
+    let meta_idx = node * m;
+    // exact integer logarithms
+    let max_bucket = match bound {
+        BucketBound::Ceil => (usize::BITS - (meta_idx - 1).leading_zeros()) as usize,
+        BucketBound::FloorPlusOne => (usize::BITS - meta_idx.leading_zeros()) as usize,
+    };
+    let mut pmf = vec![0.0; node + 1];
+    for i in 1..=max_bucket {
+        // same ranges as in `sample_parent_node`
+        let max = std::cmp::min(meta_idx, 1 << i);
+        let min = std::cmp::max(2, max >> 1);
+        let p = 1.0 / (max_bucket as f64 * (max - min + 1) as f64);
+        for r in min..=max {
+            let parent = (meta_idx - r) / m;
+            pmf[node - parent] += p;
+        }
+    }
+    pmf
+}
+
+/// Exact distribution of the distance between `node` and a parent sampled by
+/// `Ren21`: `floor(2^k)` with `k` uniform in `[0, log2(node))`.
+pub fn ren21_pmf(node: Node) -> Vec<f64> {
+    assert!(node >= 2, "node {} has no sampled parents", node);
+    let log = (node as f64).log2();
+    let mut pmf = vec![0.0; node + 1];
+    for (d, p) in pmf.iter_mut().enumerate().skip(1) {
+        let low = (d as f64).log2();
+        if low >= log {
+            break;
+        }
+        let high = ((d + 1) as f64).log2().min(log);
+        *p = (high - low) / log;
+    }
+    pmf
+}
+
+/// Histogram of `samples` distances drawn by `sampler` for `node`.
+pub fn sample_distances<S: ParentSampler>(
+    sampler: &S,
+    node: Node,
+    samples: usize,
+    seed: [u8; 32],
+) -> Vec<usize> {
+    let mut rng = ChaChaRng::from_seed(seed);
+    let mut histogram = vec![0; node + 1];
+    for _ in 0..samples {
+        histogram[sampler.back_distance(node, &mut rng)] += 1;
+    }
+    histogram
+}
+
+/// Result of Pearson's chi-square goodness of fit test.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChiSquare {
+    pub statistic: f64,
+    pub dof: usize,
+    pub p_value: f64,
+}
+
+/// Chi-square test of the `observed` histogram against the `pmf`. Adjacent
+/// bins are merged until their expected count is at least 5 for the
+/// chi-square approximation to hold.
+pub fn chi_square(observed: &[usize], pmf: &[f64]) -> ChiSquare {
+    assert_eq!(observed.len(), pmf.len());
+    let total: usize = observed.iter().sum();
+    let mut bins: Vec<(usize, f64)> = Vec::new();
+    let (mut obs, mut exp) = (0, 0.0);
+    for (&o, &p) in observed.iter().zip(pmf.iter()) {
+        obs += o;
+        exp += p * total as f64;
+        if exp >= 5.0 {
+            bins.push((obs, exp));
+            obs = 0;
+            exp = 0.0;
+        }
+    }
+    // leftovers go into the last bin
+    match bins.last_mut() {
+        Some(last) => {
+            last.0 += obs;
+            last.1 += exp;
+        }
+        None => bins.push((obs, exp)),
+    }
+
+    let statistic = bins
+        .iter()
+        .filter(|&&(_, e)| e > 0.0)
+        .map(|&(o, e)| (o as f64 - e).powi(2) / e)
+        .sum();
+    let dof = std::cmp::max(bins.len(), 2) - 1;
+    ChiSquare {
+        statistic,
+        dof,
+        p_value: chi_square_p_value(statistic, dof),
+    }
+}
+
+/// Probability of a chi-square statistic at least as extreme as the given
+/// one under the null hypothesis.
+pub fn chi_square_p_value(statistic: f64, dof: usize) -> f64 {
+    gamma_q(dof as f64 / 2.0, statistic / 2.0)
+}
+
+// Regularized upper incomplete gamma function Q(a, x), following the series
+// and continued fraction expansions of Numerical Recipes (6.2).
+fn gamma_q(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 1.0;
+    }
+    if x < a + 1.0 {
+        1.0 - gamma_p_series(a, x)
+    } else {
+        gamma_q_fraction(a, x)
+    }
+}
+
+const GAMMA_EPS: f64 = 1e-14;
+const GAMMA_ITERATIONS: usize = 10_000;
+
+fn gamma_p_series(a: f64, x: f64) -> f64 {
+    let mut sum = 1.0 / a;
+    let mut term = sum;
+    let mut n = a;
+    for _ in 0..GAMMA_ITERATIONS {
+        n += 1.0;
+        term *= x / n;
+        sum += term;
+        if term.abs() < sum.abs() * GAMMA_EPS {
+            break;
+        }
+    }
+    sum * (-x + a * x.ln() - ln_gamma(a)).exp()
+}
+
+fn gamma_q_fraction(a: f64, x: f64) -> f64 {
+    let tiny = f64::MIN_POSITIVE / GAMMA_EPS;
+    let mut b = x + 1.0 - a;
+    let mut c = 1.0 / tiny;
+    let mut d = 1.0 / b;
+    let mut h = d;
+    for i in 1..GAMMA_ITERATIONS {
+        let an = -(i as f64) * (i as f64 - a);
+        b += 2.0;
+        d = an * d + b;
+        if d.abs() < tiny {
+            d = tiny;
+        }
+        c = b + an / c;
+        if c.abs() < tiny {
+            c = tiny;
+        }
+        d = 1.0 / d;
+        let delta = d * c;
+        h *= delta;
+        if (delta - 1.0).abs() < GAMMA_EPS {
+            break;
+        }
+    }
+    (-x + a * x.ln() - ln_gamma(a)).exp() * h
+}
+
+// Lanczos approximation (g = 7, n = 9).
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        // reflection formula
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let mut sum = COEFFICIENTS[0];
+    for (i, &c) in COEFFICIENTS.iter().enumerate().skip(1) {
+        sum += c / (x + i as f64);
+    }
+    let t = x + 7.5;
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+/// Conformance of the distances sampled for one node.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeConformance {
+    pub node: Node,
+    pub samples: usize,
+    pub test: ChiSquare,
+    /// Whether the null hypothesis (the sampler follows the distribution)
+    /// was rejected at the requested significance level.
+    pub biased: bool,
+}
+
+impl fmt::Display for NodeConformance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "node {:>8}: chi2 = {:>10.2} (dof {:>6}), p-value = {:.4}{}",
+            self.node,
+            self.test.statistic,
+            self.test.dof,
+            self.test.p_value,
+            if self.biased { " -> BIASED" } else { "" }
+        )
+    }
+}
+
+/// Test the distances sampled by `sampler` against the distribution given
+/// by `pmf` (e.g., `drsample_pmf` or `ren21_pmf`) for each of the `nodes`,
+/// flagging as biased the ones with a p-value below `alpha`. Since several
+/// nodes are tested at once callers might want to correct `alpha` for the
+/// number of them (e.g., Bonferroni).
+pub fn check_sampler<S, F>(
+    sampler: &S,
+    pmf: F,
+    nodes: &[Node],
+    samples: usize,
+    alpha: f64,
+    seed: [u8; 32],
+) -> Vec<NodeConformance>
+where
+    S: ParentSampler,
+    F: Fn(Node) -> Vec<f64>,
+{
+    nodes
+        .iter()
+        .map(|&node| {
+            let observed = sample_distances(sampler, node, samples, seed);
+            let test = chi_square(&observed, &pmf(node));
+            NodeConformance {
+                node,
+                samples,
+                biased: test.p_value < alpha,
+                test,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::tests::TEST_SEED;
+    use crate::graph::{MetaBucketSampler, Ren21Sampler};
+
+    #[test]
+    fn chi_square_p_values() {
+        // reference values of the chi-square survival function
+        assert!((chi_square_p_value(3.841_458_820_694_124, 1) - 0.05).abs() < 1e-9);
+        assert!((chi_square_p_value(18.307_038_053_275_146, 10) - 0.05).abs() < 1e-9);
+        assert!((chi_square_p_value(2.0, 2) - (-1.0f64).exp()).abs() < 1e-12);
+        assert_eq!(chi_square_p_value(0.0, 5), 1.0);
+    }
+
+    #[test]
+    fn pmfs_are_distributions() {
+        for &node in [2, 3, 17, 1024, 3000].iter() {
+            for &bound in [BucketBound::Ceil, BucketBound::FloorPlusOne].iter() {
+                let pmf = drsample_pmf(node, 5, bound);
+                assert!((pmf.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+                assert_eq!(pmf[0], 0.0);
+            }
+            let pmf = ren21_pmf(node);
+            assert!((pmf.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn bucket_bounds() {
+        // the distance 2 is drawn from the buckets 1 (with probability 1)
+        // and 2 (1/3), each picked with probability 1/max_bucket
+        let max_bucket = |node, bound| {
+            let pmf = drsample_pmf(node, 1, bound);
+            (4.0 / 3.0 / pmf[2]).round() as usize
+        };
+        assert_eq!(max_bucket(1 << 10, BucketBound::Ceil), 10);
+        assert_eq!(max_bucket(1 << 10, BucketBound::FloorPlusOne), 11);
+        assert_eq!(max_bucket((1 << 10) + 1, BucketBound::Ceil), 11);
+        assert_eq!(max_bucket((1 << 10) + 1, BucketBound::FloorPlusOne), 11);
+        // exact where the f32 bound of the sampler rounds down
+        assert_eq!(crate::graph::max_bucket((1 << 21) + 1), 21);
+        assert_eq!(max_bucket((1 << 21) + 1, BucketBound::Ceil), 22);
+    }
+
+    #[test]
+    fn samplers_conformance() {
+        let nodes = [5, 100, 1000, 1024];
+        let samples = 20_000;
+        let alpha = 0.001 / nodes.len() as f64;
+
+        let drsample = MetaBucketSampler { degree: 6 };
+        let pmf = |node| drsample_pmf(node, 5, BucketBound::Ceil);
+        let report = check_sampler(&drsample, pmf, &nodes, samples, alpha, TEST_SEED);
+        assert!(report.iter().all(|r| !r.biased), "{:?}", report);
+
+        let ren21 = Ren21Sampler { degree: 6 };
+        let report = check_sampler(&ren21, ren21_pmf, &nodes, samples, alpha, TEST_SEED);
+        assert!(report.iter().all(|r| !r.biased), "{:?}", report);
+
+        // the CCS bucket bound differs from ours for powers of two and gets
+        // detected with enough samples (`1024 * 5` is not a power of two but
+        // `1024 * 4` is)
+        let drsample = MetaBucketSampler { degree: 5 };
+        let pmf = |node| drsample_pmf(node, 4, BucketBound::FloorPlusOne);
+        let report = check_sampler(&drsample, pmf, &[1024], samples, alpha, TEST_SEED);
+        assert!(report[0].biased);
+    }
+}
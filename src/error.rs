@@ -0,0 +1,136 @@
+use std::error;
+use std::fmt;
+use std::io;
+
+use crate::attacks::AttackAlgo;
+use crate::graph::{DRGAlgo, ExclusionSetError};
+use crate::import::ImportError;
+use crate::pebbling::PebblingError;
+use crate::stream::StreamError;
+
+/// Error of the library: the failures of graph construction, loading,
+/// attacks and result export, so applications embedding it can handle bad
+/// inputs instead of aborting. The errors of the modules with their own
+/// type (e.g., `ImportError`) are wrapped as they are.
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Csv(csv::Error),
+    Import(ImportError),
+    Stream(StreamError),
+    ExclusionSet(ExclusionSetError),
+    Pebbling(PebblingError),
+    /// The graph can't be generated from its spec.
+    Unsupported(DRGAlgo),
+    /// Invalid parameters of the graph construction.
+    InvalidSpec(String),
+    /// The nodes of a graph of `size` don't fit in an index type of `bits`.
+    IndexOverflow {
+        size: usize,
+        bits: usize,
+    },
+    /// Attack function called with an attack it doesn't implement.
+    InvalidAttack(Box<AttackAlgo>),
+    /// Invalid parameters of an attack.
+    InvalidParams(String),
+    /// Valiant's attack used all the partitions without reaching its target.
+    NoMorePartitions,
+    /// The partition chosen by `ValiantAB16` didn't halve the depth of G-S
+    /// (from `depth` to `new_depth`) as the paper claims it always does.
+    NotHalved {
+        depth: usize,
+        new_depth: usize,
+    },
+    /// Greedy excluded all the nodes without reaching its target.
+    NoMoreNodes,
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "io error: {}", e),
+            Error::Json(e) => write!(f, "json error: {}", e),
+            Error::Csv(e) => write!(f, "csv error: {}", e),
+            Error::Import(e) => write!(f, "import error: {}", e),
+            Error::Stream(e) => write!(f, "stream error: {}", e),
+            Error::ExclusionSet(e) => write!(f, "exclusion set error: {}", e),
+            Error::Pebbling(e) => write!(f, "pebbling error: {}", e),
+            Error::Unsupported(algo) => write!(f, "can't generate {:?} graphs", algo),
+            Error::InvalidSpec(msg) => write!(f, "invalid graph spec: {}", msg),
+            Error::IndexOverflow { size, bits } => write!(
+                f,
+                "a graph of size {} can't be indexed with {} bits",
+                size, bits
+            ),
+            Error::InvalidAttack(attack) => write!(f, "invalid attack option: {}", attack),
+            Error::InvalidParams(msg) => write!(f, "invalid attack parameters: {}", msg),
+            Error::NoMorePartitions => write!(f, "no more partitions to use"),
+            Error::NotHalved { depth, new_depth } => write!(
+                f,
+                "the chosen partition only reduced the depth from {} to {}",
+                depth, new_depth
+            ),
+            Error::NoMoreNodes => write!(f, "no more nodes to remove"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Json(e) => Some(e),
+            Error::Csv(e) => Some(e),
+            Error::Import(e) => Some(e),
+            Error::Stream(e) => Some(e),
+            Error::ExclusionSet(e) => Some(e),
+            Error::Pebbling(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+impl From<csv::Error> for Error {
+    fn from(e: csv::Error) -> Self {
+        Error::Csv(e)
+    }
+}
+
+impl From<ImportError> for Error {
+    fn from(e: ImportError) -> Self {
+        Error::Import(e)
+    }
+}
+
+impl From<StreamError> for Error {
+    fn from(e: StreamError) -> Self {
+        Error::Stream(e)
+    }
+}
+
+impl From<ExclusionSetError> for Error {
+    fn from(e: ExclusionSetError) -> Self {
+        Error::ExclusionSet(e)
+    }
+}
+
+impl From<PebblingError> for Error {
+    fn from(e: PebblingError) -> Self {
+        Error::Pebbling(e)
+    }
+}
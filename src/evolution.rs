@@ -0,0 +1,312 @@
+use std::time::{Duration, Instant};
+
+use log::debug;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaChaRng;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::annealing::{InitialSet, Objective};
+use crate::attacks::{count_paths, AttackAlgo, GreedyParams};
+use crate::error::{Error, Result};
+use crate::graph::{ExclusionSet, Graph, Node, NodeIndex};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvolutionParams {
+    /// Number of individuals in each generation.
+    pub population: usize,
+    /// Best individuals copied unchanged to the next generation.
+    pub elite: usize,
+    /// Number of individuals competing to be selected as a parent.
+    pub tournament: usize,
+    /// Guided mutations applied to each offspring.
+    pub mutations: usize,
+    /// Length of the paths counted by `count_paths` to guide the mutations.
+    pub length: usize,
+    pub generations: usize,
+    /// The evolution stops after the generation that exceeds it.
+    pub time_budget: Duration,
+    pub initial: InitialSet,
+    pub seed: [u8; 32],
+}
+
+impl Default for EvolutionParams {
+    fn default() -> Self {
+        EvolutionParams {
+            population: 32,
+            elite: 4,
+            tournament: 3,
+            mutations: 2,
+            length: 8,
+            generations: 50,
+            time_budget: Duration::from_secs(60),
+            initial: InitialSet::Empty,
+            seed: [0; 32],
+        }
+    }
+}
+
+/// Best individual found by `evolve`.
+#[derive(Debug, Clone)]
+pub struct EvolutionReport {
+    pub set: ExclusionSet,
+    pub depth: usize,
+    /// Generations evolved within the budget.
+    pub generations: usize,
+    pub elapsed: Duration,
+}
+
+#[derive(Clone)]
+struct Individual {
+    set: ExclusionSet,
+    depth: usize,
+}
+
+impl Individual {
+    fn new<I: NodeIndex>(g: &Graph<I>, set: ExclusionSet) -> Self {
+        let depth = g.depth_exclude(&set);
+        Individual { set, depth }
+    }
+
+    fn rank(&self, objective: Objective) -> (usize, usize) {
+        objective.rank(self.set.size(), self.depth)
+    }
+}
+
+/// Evolutionary search of an exclusion set for the `objective`, starting
+/// from a population of mutations of the `initial` set. Each generation
+/// keeps its `elite` and breeds the rest in parallel from parents selected by
+/// tournament, through a crossover that keeps whole blocks of nodes aligned
+/// to a power of two (so the edges of the lower msbd partitions, which stay
+/// inside those blocks, are inherited together as in Valiant's attack) and
+/// mutations that add nodes with probability proportional to their number
+/// of incident paths (`count_paths`). Every offspring gets its own RNG
+/// derived from the seed, so the result only depends on the number of
+/// generations evolved within the time budget.
+pub fn evolve<I: NodeIndex>(
+    g: &Graph<I>,
+    initial: ExclusionSet,
+    objective: Objective,
+    p: &EvolutionParams,
+) -> Result<EvolutionReport> {
+    if p.population <= p.elite {
+        return Err(Error::InvalidParams("no room for offspring".to_string()));
+    }
+    let start = Instant::now();
+    let gp = GreedyParams {
+        length: p.length,
+        ..GreedyParams::default()
+    };
+
+    let mut population: Vec<Individual> = (0..p.population)
+        .into_par_iter()
+        .map(|i| {
+            let mut set = initial.clone();
+            if i > 0 {
+                let mut rng = offspring_rng(&p.seed, 0, i);
+                mutate(g, &mut set, objective, &gp, p.mutations, &mut rng);
+            }
+            Individual::new(g, set)
+        })
+        .collect();
+    population.sort_by_key(|ind| ind.rank(objective));
+
+    let mut generations = 0;
+    while generations < p.generations && start.elapsed() < p.time_budget {
+        generations += 1;
+        let offspring: Vec<Individual> = (p.elite..p.population)
+            .into_par_iter()
+            .map(|i| {
+                let mut rng = offspring_rng(&p.seed, generations, i);
+                let a = tournament(&population, p.tournament, objective, &mut rng);
+                let b = tournament(&population, p.tournament, objective, &mut rng);
+                let mut set = crossover(&a.set, &b.set, g.size(), &mut rng);
+                mutate(g, &mut set, objective, &gp, p.mutations, &mut rng);
+                Individual::new(g, set)
+            })
+            .collect();
+        population.truncate(p.elite);
+        population.extend(offspring);
+        population.sort_by_key(|ind| ind.rank(objective));
+        debug!(
+            "\t-> generation {}: best |S| = {}, depth(G-S) = {}",
+            generations,
+            population[0].set.size(),
+            population[0].depth
+        );
+    }
+
+    let best = population.swap_remove(0);
+    Ok(EvolutionReport {
+        set: best.set,
+        depth: best.depth,
+        generations,
+        elapsed: start.elapsed(),
+    })
+}
+
+fn offspring_rng(seed: &[u8; 32], generation: usize, index: usize) -> ChaChaRng {
+    let mut d = blake3::Hasher::new();
+    d.update(seed);
+    d.update(&generation.to_be_bytes()[..]);
+    d.update(&index.to_be_bytes()[..]);
+    let mut local_seed: [u8; 32] = [0; 32];
+    local_seed.copy_from_slice(&d.finalize().as_bytes()[..]);
+    ChaChaRng::from_seed(local_seed)
+}
+
+fn tournament<'a>(
+    population: &'a [Individual],
+    size: usize,
+    objective: Objective,
+    rng: &mut ChaChaRng,
+) -> &'a Individual {
+    (0..std::cmp::max(size, 1))
+        .map(|_| &population[rng.gen_range(0, population.len())])
+        .min_by_key(|ind| ind.rank(objective))
+        .unwrap()
+}
+
+// Take each block of `2^level` nodes (for a random level) from one of the
+// parents.
+fn crossover(a: &ExclusionSet, b: &ExclusionSet, size: usize, rng: &mut ChaChaRng) -> ExclusionSet {
+    let max_level = (size as f64).log2().ceil() as usize;
+    let block = 1 << rng.gen_range(0, max_level + 1);
+    let mut child = ExclusionSet::new_with_size(size);
+    for start in (0..size).step_by(block) {
+        let parent = if rng.gen::<bool>() { a } else { b };
+        (start..std::cmp::min(start + block, size))
+            .filter(|&node| parent.contains(node))
+            .for_each(|node| child.insert(node));
+    }
+    child
+}
+
+// Each mutation adds a node of G-S picked with probability proportional to
+// its incident paths, unless the set already meets a depth target (then it
+// removes a random node to try a smaller one) or is at the size budget (then
+// it swaps a random node for the new one).
+fn mutate<I: NodeIndex>(
+    g: &Graph<I>,
+    set: &mut ExclusionSet,
+    objective: Objective,
+    gp: &GreedyParams,
+    mutations: usize,
+    rng: &mut ChaChaRng,
+) {
+    for _ in 0..mutations {
+        let add = match objective {
+            Objective::MinSize { depth } => g.depth_exclude(set) > depth,
+            Objective::MinDepth { size } => {
+                while set.size() >= size && set.size() > 0 {
+                    remove_random(set, rng);
+                }
+                size > 0
+            }
+        };
+        if !add {
+            remove_random(set, rng);
+            continue;
+        }
+        let incidents = count_paths(g, set, gp);
+        let total: usize = incidents.iter().map(|pair| pair.1).sum();
+        if total == 0 {
+            return;
+        }
+        let mut target = rng.gen_range(0, total);
+        for pair in incidents.iter() {
+            if target < pair.1 {
+                set.insert(pair.0);
+                break;
+            }
+            target -= pair.1;
+        }
+    }
+}
+
+fn remove_random(set: &mut ExclusionSet, rng: &mut ChaChaRng) {
+    let members: Vec<Node> = set.iter().collect();
+    if !members.is_empty() {
+        set.remove(members[rng.gen_range(0, members.len())]);
+    }
+}
+
+pub(crate) fn evolution_reduce<I: NodeIndex>(g: &Graph<I>, d: AttackAlgo) -> Result<ExclusionSet> {
+    let (objective, p) = match d {
+        AttackAlgo::EvolutionDepth(depth, p) => (Objective::MinSize { depth }, p),
+        AttackAlgo::EvolutionSize(size, p) => (Objective::MinDepth { size }, p),
+        _ => return Err(Error::InvalidAttack(Box::new(d))),
+    };
+    let initial = p.initial.build(g, objective)?;
+    let report = evolve(g, initial, objective, &p)?;
+    debug!(
+        "\t-> evolved {} generations in {:?}",
+        report.generations, report.elapsed
+    );
+    Ok(report.set)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::attacks::depth_reduce;
+    use crate::graph::{self, DRGAlgo};
+
+    #[test]
+    fn evolution_crossover() {
+        let g = Graph::new(8, graph::tests::TEST_SEED, DRGAlgo::MetaBucket(3)).unwrap();
+        let a = ExclusionSet::from_nodes(&g, vec![0, 1, 2, 5]);
+        let b = ExclusionSet::from_nodes(&g, vec![1, 2, 6, 7]);
+        let mut rng = ChaChaRng::from_seed(graph::tests::TEST_SEED);
+        for _ in 0..20 {
+            let child = crossover(&a, &b, g.size(), &mut rng);
+            // the nodes in both parents are always inherited and the child
+            // doesn't have any node outside of them
+            assert!(child.contains(1) && child.contains(2));
+            assert!((0..8).all(|n| !child.contains(n) || a.contains(n) || b.contains(n)));
+        }
+        // whole blocks from the same parent: with a single block of 8 nodes
+        // (level 3) the child is one of the parents
+        let children: Vec<ExclusionSet> = (0..50)
+            .map(|_| crossover(&a, &b, g.size(), &mut rng))
+            .collect();
+        assert!(children.iter().any(|c| c == &a || c == &b));
+    }
+
+    #[test]
+    fn evolution_attack() {
+        let size = 256;
+        let g = Graph::new(size, graph::tests::TEST_SEED, DRGAlgo::MetaBucket(3)).unwrap();
+        let depth = size / 8;
+        let p = EvolutionParams {
+            population: 8,
+            elite: 2,
+            generations: 5,
+            initial: InitialSet::Valiant,
+            seed: graph::tests::TEST_SEED,
+            ..EvolutionParams::default()
+        };
+        let valiant = depth_reduce(&g, AttackAlgo::ValiantDepth(depth)).unwrap();
+        let s = depth_reduce(&g, AttackAlgo::EvolutionDepth(depth, p.clone())).unwrap();
+        // the elite keeps the valiant set if nothing better is found
+        assert!(g.depth_exclude(&s) <= depth);
+        assert!(s.size() <= valiant.size());
+        // deterministic for the same number of generations
+        assert_eq!(
+            s,
+            depth_reduce(&g, AttackAlgo::EvolutionDepth(depth, p.clone())).unwrap()
+        );
+
+        let budget = valiant.size() / 2;
+        let report = evolve(
+            &g,
+            ExclusionSet::new(&g),
+            Objective::MinDepth { size: budget },
+            &p,
+        )
+        .unwrap();
+        assert!(report.set.size() <= budget);
+        assert_eq!(report.depth, g.depth_exclude(&report.set));
+        assert!(report.depth < g.depth());
+    }
+}
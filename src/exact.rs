@@ -0,0 +1,396 @@
+use std::io::{self, Write};
+
+use crate::error::{Error, Result};
+use crate::graph::{ExclusionSet, Graph, Node, NodeIndex};
+
+/// Result of the exact search of a minimum depth-reducing set.
+#[derive(Debug, Clone)]
+pub struct ExactSolution {
+    pub set: ExclusionSet,
+    /// Whether the search finished within its budget, i.e., `set` is
+    /// guaranteed to be minimum (otherwise it's only the best one found).
+    pub optimal: bool,
+    /// Lower bound of the size of a minimum set (the one of `set` if the
+    /// search finished), to know how far from optimal the solution is.
+    pub lower_bound: usize,
+    /// Number of nodes of the search tree explored.
+    pub explored: usize,
+}
+
+/// Find a minimum set `S` such that `depth(G-S) <= depth` with a Russian
+/// doll search, practical for a few hundred nodes of regular graphs (e.g.,
+/// `KConnector`) but only around a hundred of random DRGs (e.g., `MetaBucket`
+/// with a depth of 8), use `write_lp` beyond that. The suffixes
+/// `G[k..n]` of the graph are solved from the smallest one: each of them is a
+/// search deciding its nodes in topological order (which keeps the exact
+/// depth of the nodes left in the graph) for a set of the size of the minimum
+/// of `G[k+1..n]`, as the minimum of `G[k..n]` is either that one or one more.
+///
+/// The search is bounded with the minimums already known: for every `j`
+/// the nodes of the set in `G[j..n]` have to reduce its depth as well, so
+/// the nodes still undecided in `G[i..j]` need one node of the set for each
+/// disjoint path of `depth + 1` edges (counting the depth they inherit from
+/// the nodes decided before), and `G[j..n]` its minimum on top of that. Each
+/// suffix also records the largest depth its first node can inherit without
+/// raising its minimum, to add one more node past that.
+///
+/// The `initial` solution (e.g., the output of a heuristic) skips the search
+/// of the suffixes where it already has the minimum size. The search gives
+/// up after exploring `budget` nodes, in which case the best solution found
+/// is returned, not marked as optimal, along with the minimum of the largest
+/// suffix solved as lower bound.
+pub fn min_depth_reducing_set<I: NodeIndex>(
+    g: &Graph<I>,
+    depth: usize,
+    initial: Option<&ExclusionSet>,
+    budget: Option<usize>,
+) -> Result<ExactSolution> {
+    if let Some(set) = initial {
+        if g.depth_exclude(set) > depth {
+            return Err(Error::InvalidParams(format!(
+                "initial set doesn't reduce the depth to {}",
+                depth
+            )));
+        }
+    }
+    let mut search = Search {
+        g,
+        depth,
+        budget,
+        minimums: vec![0; g.size() + 1],
+        thresholds: vec![NONE; g.size() + 1],
+        inherited: vec![0; g.size()],
+        updates: Vec::new(),
+        paths: vec![(0, NONE); g.size()],
+        removed: Vec::new(),
+        explored: 0,
+        exhausted: false,
+    };
+    // minimum set of the last suffix solved
+    let mut best: Vec<Node> = Vec::new();
+    for start in (0..g.size()).rev() {
+        let size = search.minimums[start + 1];
+        let restricted: Option<Vec<Node>> =
+            initial.map(|set| set.iter().filter(|&node| node >= start).collect());
+        match restricted {
+            Some(nodes) if nodes.len() <= size => best = nodes,
+            _ => {
+                search.removed.clear();
+                if search.find(start, size) {
+                    best = search.removed.clone();
+                    search.thresholds[start] = search.threshold(start, size);
+                } else if !search.exhausted {
+                    best.push(start);
+                }
+            }
+        }
+        if search.exhausted {
+            // removing all the nodes before the suffix is also a solution
+            let mut set = ExclusionSet::from_nodes(g, best);
+            (0..=start).for_each(|node| set.insert(node));
+            return Ok(ExactSolution {
+                set: match initial {
+                    Some(initial) if initial.size() <= set.size() => initial.clone(),
+                    _ => set,
+                },
+                optimal: false,
+                lower_bound: size,
+                explored: search.explored,
+            });
+        }
+        search.minimums[start] = best.len();
+    }
+    Ok(ExactSolution {
+        lower_bound: best.len(),
+        set: ExclusionSet::from_nodes(g, best),
+        optimal: true,
+        explored: search.explored,
+    })
+}
+
+/// Write the ILP formulation of the minimum depth-reducing set in the LP
+/// format of CPLEX (read by most solvers, e.g., CBC, HiGHS or Gurobi), for
+/// the graphs out of reach of `min_depth_reducing_set`. Every node `v` has a
+/// binary `x_v` (whether it's in the set) and a level `l_v` in `[0, depth]`
+/// that has to grow along the edges left in G-S.
+pub fn write_lp<I: NodeIndex, W: Write>(g: &Graph<I>, depth: usize, mut w: W) -> io::Result<()> {
+    writeln!(w, "\\ minimum set S such that depth(G-S) <= {}", depth)?;
+    writeln!(w, "Minimize")?;
+    write!(w, " size:")?;
+    for node in 0..g.size() {
+        if node == 0 {
+            write!(w, " x{}", node)?;
+        } else {
+            // keep the lines short, some readers limit their length
+            if node % 16 == 0 {
+                writeln!(w)?;
+            }
+            write!(w, " + x{}", node)?;
+        }
+    }
+    writeln!(w)?;
+    writeln!(w, "Subject To")?;
+    // l_child >= l_parent + 1 unless one of them is removed (the levels
+    // differ by at most `depth` so `depth + 1` relaxes the constraint)
+    let mut result = Ok(());
+    g.for_each_edge(|edge| {
+        if result.is_ok() {
+            result = writeln!(
+                w,
+                " e{}_{}: l{} - l{} + {} x{} + {} x{} >= 1",
+                edge.parent,
+                edge.child,
+                edge.child,
+                edge.parent,
+                depth + 1,
+                edge.parent,
+                depth + 1,
+                edge.child
+            );
+        }
+    });
+    result?;
+    writeln!(w, "Bounds")?;
+    for node in 0..g.size() {
+        writeln!(w, " 0 <= l{} <= {}", node, depth)?;
+    }
+    writeln!(w, "Binary")?;
+    for node in 0..g.size() {
+        writeln!(w, " x{}", node)?;
+    }
+    writeln!(w, "General")?;
+    for node in 0..g.size() {
+        writeln!(w, " l{}", node)?;
+    }
+    writeln!(w, "End")
+}
+
+// Node marked as used by a path in `Search::bound` (or the lack of a parent
+// in a path).
+const NONE: usize = usize::MAX;
+
+struct Search<'a, I> {
+    g: &'a Graph<I>,
+    depth: usize,
+    budget: Option<usize>,
+    // minimum size of the set of each suffix solved so far
+    minimums: Vec<usize>,
+    // largest depth the first node of each suffix can inherit from the nodes
+    // before it without raising the minimum of the suffix
+    thresholds: Vec<usize>,
+    // depth each node inherits from its parents left in the current suffix
+    // (the depth it would have if left in the graph once they're decided)
+    inherited: Vec<usize>,
+    // changes to `inherited` to undo when backtracking
+    updates: Vec<(Node, usize)>,
+    // depth and parent of the nodes in the paths of `bound`
+    paths: Vec<(usize, Node)>,
+    // nodes removed in the current suffix
+    removed: Vec<Node>,
+    explored: usize,
+    exhausted: bool,
+}
+
+impl<'a, I: NodeIndex> Search<'a, I> {
+    // Whether the suffix has a set of at most `size` nodes when the nodes up
+    // to `node` are already decided (the search is `exhausted` if the budget
+    // runs out).
+    fn find(&mut self, node: Node, size: usize) -> bool {
+        if node == self.g.size() {
+            return true;
+        }
+        if self.budget.is_some_and(|budget| self.explored >= budget) {
+            self.exhausted = true;
+            return false;
+        }
+        self.explored += 1;
+
+        if self.inherited[node] <= self.depth {
+            let mark = self.keep(node);
+            let found =
+                self.removed.len() + self.bound(node + 1) <= size && self.find(node + 1, size);
+            self.undo(mark);
+            if found {
+                return true;
+            }
+        }
+        self.removed.push(node);
+        if self.removed.len() + self.bound(node + 1) <= size && self.find(node + 1, size) {
+            return true;
+        }
+        self.removed.pop();
+        false
+    }
+
+    // Threshold of the suffix starting at `start` with a minimum of `size`,
+    // searching again with its first node inheriting increasing depths.
+    fn threshold(&mut self, start: Node, size: usize) -> usize {
+        let (mut low, mut high) = (0, self.depth + 1);
+        while high - low > 1 {
+            let middle = (low + high) / 2;
+            self.removed.clear();
+            self.inherited[start] = middle;
+            if self.find(start, size) {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        self.inherited[start] = 0;
+        low
+    }
+
+    // Leave `node` in the graph, returning the mark to undo it.
+    fn keep(&mut self, node: Node) -> usize {
+        let mark = self.updates.len();
+        let depth = self.inherited[node] + 1;
+        for child in self.g.children()[node].iter().map(|c| c.node()) {
+            if self.inherited[child] < depth {
+                self.updates.push((child, self.inherited[child]));
+                self.inherited[child] = depth;
+            }
+        }
+        mark
+    }
+
+    fn undo(&mut self, mark: usize) {
+        for (child, inherited) in self.updates.drain(mark..).rev() {
+            self.inherited[child] = inherited;
+        }
+    }
+
+    // Lower bound of the nodes of the set in the undecided nodes from `node`:
+    // the disjoint paths exceeding the depth in `G[node..j]` (taking the ones
+    // that end first) plus the minimum of `G[j..n]` (one more if `j` already
+    // inherits more than its threshold), for the best `j`.
+    fn bound(&mut self, node: Node) -> usize {
+        let mut bound = self.minimums[node];
+        let mut paths = 0;
+        for next in node..self.g.size() {
+            if self.inherited[next] > self.thresholds[next] {
+                bound = std::cmp::max(bound, paths + self.minimums[next] + 1);
+            }
+            let mut longest = (self.inherited[next], NONE);
+            for parent in self.g.parents()[next].iter().map(|p| p.node()) {
+                let depth = self.paths[parent].0;
+                if parent >= node && depth != NONE && depth + 1 > longest.0 {
+                    longest = (depth + 1, parent);
+                }
+            }
+            if longest.0 > self.depth {
+                paths += 1;
+                bound = std::cmp::max(bound, paths + self.minimums[next + 1]);
+                // the nodes of the path can't be used by the next ones
+                self.paths[next].0 = NONE;
+                let mut parent = longest.1;
+                while parent != NONE {
+                    self.paths[parent].0 = NONE;
+                    parent = self.paths[parent].1;
+                }
+            } else {
+                self.paths[next] = longest;
+            }
+        }
+        bound
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::attacks::{depth_reduce, AttackAlgo, GreedyParams};
+    use crate::graph::{self, DRGAlgo, GraphSpec};
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    #[test]
+    fn exact_line() {
+        // a line of 10 nodes needs 2 nodes removed to have segments of at
+        // most 3 nodes (depth 2), e.g., {3, 7}
+        let g = Graph::new(10, graph::tests::TEST_SEED, DRGAlgo::KConnector(1)).unwrap();
+        let solution = min_depth_reducing_set(&g, 2, None, None).unwrap();
+        assert!(solution.optimal);
+        assert_eq!(solution.set.size(), 2);
+        assert!(g.depth_exclude(&solution.set) <= 2);
+
+        // the budget stops the search before proving optimality, with the
+        // minimum of the suffixes solved as lower bound
+        let solution = min_depth_reducing_set(&g, 2, None, Some(5)).unwrap();
+        assert!(!solution.optimal);
+        assert!(solution.lower_bound <= 2);
+        assert!(g.depth_exclude(&solution.set) <= 2);
+    }
+
+    #[test]
+    fn exact_lp_format() {
+        let g = graph::tests::graph_from(vec![vec![], vec![0], vec![0, 1]]);
+        let mut out = Vec::new();
+        write_lp(&g, 1, &mut out).unwrap();
+        let lp = String::from_utf8(out).unwrap();
+        assert!(lp.contains(" size: x0 + x1 + x2\n"));
+        assert!(lp.contains(" e0_2: l2 - l0 + 2 x0 + 2 x2 >= 1\n"));
+        assert!(lp.contains(" 0 <= l2 <= 1\n"));
+        assert!(lp.ends_with("End\n"));
+    }
+
+    #[test]
+    fn exact_against_heuristics() {
+        let graphs = vec![
+            (
+                Graph::new(32, graph::tests::TEST_SEED, DRGAlgo::KConnector(2)).unwrap(),
+                6,
+            ),
+            (
+                Graph::new(64, graph::tests::TEST_SEED, DRGAlgo::MetaBucket(3)).unwrap(),
+                8,
+            ),
+            (
+                Graph::new(256, graph::tests::TEST_SEED, DRGAlgo::KConnector(2)).unwrap(),
+                16,
+            ),
+            (
+                Graph::new(256, graph::tests::TEST_SEED, DRGAlgo::KConnector(4)).unwrap(),
+                8,
+            ),
+        ];
+        for (g, depth) in graphs.into_iter() {
+            let params = GreedyParams {
+                k: 1,
+                radius: 0,
+                length: 8,
+                ..GreedyParams::default()
+            };
+            let greedy = depth_reduce(&g, AttackAlgo::GreedyDepth(depth, params)).unwrap();
+            let valiant = depth_reduce(&g, AttackAlgo::ValiantDepth(depth)).unwrap();
+            let exact = min_depth_reducing_set(&g, depth, Some(&greedy), None).unwrap();
+            assert!(exact.optimal);
+            assert!(g.depth_exclude(&exact.set) <= depth);
+            assert!(exact.set.size() <= greedy.size());
+            assert!(exact.set.size() <= valiant.size());
+            // starting from scratch reaches the same minimum
+            let scratch = min_depth_reducing_set(&g, depth, None, None).unwrap();
+            assert_eq!(scratch.set.size(), exact.set.size());
+        }
+    }
+
+    #[test]
+    fn exact_node_index() {
+        // same search on a graph stored with u32 nodes
+        let spec = GraphSpec {
+            size: 64,
+            seed: graph::tests::TEST_SEED,
+            algo: DRGAlgo::MetaBucket(3),
+        };
+        let g = Graph::new_from_rng(spec, &mut ChaChaRng::from_seed(spec.seed)).unwrap();
+        let g32 = Graph::<u32>::generate(spec, &mut ChaChaRng::from_seed(spec.seed)).unwrap();
+        let exact = min_depth_reducing_set(&g, 8, None, None).unwrap();
+        let exact32 = min_depth_reducing_set(&g32, 8, None, None).unwrap();
+        assert_eq!(exact32.set, exact.set);
+        assert_eq!(exact32.explored, exact.explored);
+
+        let (mut lp, mut lp32) = (Vec::new(), Vec::new());
+        write_lp(&g, 8, &mut lp).unwrap();
+        write_lp(&g32, 8, &mut lp32).unwrap();
+        assert_eq!(lp32, lp);
+    }
+}
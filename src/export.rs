@@ -0,0 +1,258 @@
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use crate::graph::{Edge, ExclusionSet, FastHashSet, Graph, Node, NodeIndex};
+
+const EXCLUDED_COLOR: &str = "red";
+const PATH_COLOR: &str = "blue";
+
+/// Export a (small) graph to formats readable by external visualization tools
+/// (DOT, GraphML and CSV edge lists), optionally highlighting the nodes of an
+/// exclusion set `S` and a longest path of G-S.
+pub struct Exporter<'a, I = Node> {
+    graph: &'a Graph<I>,
+    excluded: Option<&'a ExclusionSet>,
+    // nodes and edges of the highlighted longest path
+    path: FastHashSet<Node>,
+    path_edges: FastHashSet<Edge<I>>,
+}
+
+impl<'a, I: NodeIndex> Exporter<'a, I> {
+    pub fn new(graph: &'a Graph<I>) -> Self {
+        Exporter {
+            graph,
+            excluded: None,
+            path: FastHashSet::default(),
+            path_edges: FastHashSet::default(),
+        }
+    }
+
+    /// Colour the nodes in `set`.
+    pub fn exclusion(mut self, set: &'a ExclusionSet) -> Self {
+        self.excluded = Some(set);
+        self
+    }
+
+    /// Highlight a longest path of G-S (of G if no exclusion set was given).
+    pub fn longest_path(mut self) -> Self {
+        let path = match self.excluded {
+            Some(set) => self.graph.longest_path_exclude(set),
+            None => self
+                .graph
+                .longest_path_exclude(&ExclusionSet::new(self.graph)),
+        };
+        self.path_edges = path
+            .windows(2)
+            .map(|w| Edge::new(I::from_node(w[0]), I::from_node(w[1])))
+            .collect();
+        self.path = path.into_iter().collect();
+        self
+    }
+
+    fn is_excluded(&self, node: Node) -> bool {
+        self.excluded.is_some_and(|s| s.contains(node))
+    }
+
+    fn node_color(&self, node: Node) -> Option<&'static str> {
+        if self.is_excluded(node) {
+            Some(EXCLUDED_COLOR)
+        } else if self.path.contains(&node) {
+            Some(PATH_COLOR)
+        } else {
+            None
+        }
+    }
+
+    pub fn to_dot<W: Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(w, "digraph G {{")?;
+        writeln!(w, "  rankdir=LR;")?;
+        for node in 0..self.graph.size() {
+            match self.node_color(node) {
+                Some(color) => writeln!(
+                    w,
+                    "  {} [style=filled, fillcolor={}, fontcolor=white];",
+                    node, color
+                )?,
+                None => writeln!(w, "  {};", node)?,
+            }
+        }
+        let mut result = Ok(());
+        self.graph.for_each_edge(|edge| {
+            if result.is_err() {
+                return;
+            }
+            result = if self.path_edges.contains(edge) {
+                writeln!(
+                    w,
+                    "  {} -> {} [color={}, penwidth=2];",
+                    edge.parent, edge.child, PATH_COLOR
+                )
+            } else if self.is_excluded(edge.parent.node()) || self.is_excluded(edge.child.node()) {
+                writeln!(w, "  {} -> {} [style=dotted];", edge.parent, edge.child)
+            } else {
+                writeln!(w, "  {} -> {};", edge.parent, edge.child)
+            };
+        });
+        result?;
+        writeln!(w, "}}")
+    }
+
+    pub fn to_graphml<W: Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            w,
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+        )?;
+        writeln!(
+            w,
+            r#"  <key id="excluded" for="node" attr.name="excluded" attr.type="boolean"/>"#
+        )?;
+        writeln!(
+            w,
+            r#"  <key id="color" for="node" attr.name="color" attr.type="string"/>"#
+        )?;
+        writeln!(
+            w,
+            r#"  <key id="path" for="edge" attr.name="path" attr.type="boolean"/>"#
+        )?;
+        writeln!(w, r#"  <graph id="G" edgedefault="directed">"#)?;
+        for node in 0..self.graph.size() {
+            writeln!(w, r#"    <node id="n{}">"#, node)?;
+            writeln!(
+                w,
+                r#"      <data key="excluded">{}</data>"#,
+                self.is_excluded(node)
+            )?;
+            if let Some(color) = self.node_color(node) {
+                writeln!(w, r#"      <data key="color">{}</data>"#, color)?;
+            }
+            writeln!(w, "    </node>")?;
+        }
+        let mut result = Ok(());
+        self.graph.for_each_edge(|edge| {
+            if result.is_err() {
+                return;
+            }
+            result = writeln!(
+                w,
+                r#"    <edge source="n{}" target="n{}"><data key="path">{}</data></edge>"#,
+                edge.parent,
+                edge.child,
+                self.path_edges.contains(edge)
+            );
+        });
+        result?;
+        writeln!(w, "  </graph>")?;
+        writeln!(w, "</graphml>")
+    }
+
+    /// CSV edge list with one `parent,child` edge per row, along with
+    /// whether each end is excluded and whether the edge is on the path.
+    pub fn to_csv<W: Write>(&self, w: W) -> Result<(), csv::Error> {
+        #[derive(Serialize)]
+        struct Record {
+            parent: Node,
+            child: Node,
+            parent_excluded: bool,
+            child_excluded: bool,
+            path: bool,
+        }
+        let mut wtr = csv::Writer::from_writer(w);
+        let mut result = Ok(());
+        self.graph.for_each_edge(|edge| {
+            if result.is_err() {
+                return;
+            }
+            result = wtr.serialize(Record {
+                parent: edge.parent.node(),
+                child: edge.child.node(),
+                parent_excluded: self.is_excluded(edge.parent.node()),
+                child_excluded: self.is_excluded(edge.child.node()),
+                path: self.path_edges.contains(edge),
+            });
+        });
+        result?;
+        wtr.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::{self, DRGAlgo, GraphSpec};
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    #[test]
+    fn export_dot() {
+        let g = graph::tests::graph_from(vec![vec![], vec![0], vec![0, 1], vec![2]]);
+        let s = ExclusionSet::from_nodes(&g, vec![1]);
+        let mut out = Vec::new();
+        Exporter::new(&g)
+            .exclusion(&s)
+            .longest_path()
+            .to_dot(&mut out)
+            .unwrap();
+        let dot = String::from_utf8(out).unwrap();
+        assert!(dot.starts_with("digraph G {"));
+        assert!(dot.contains("1 [style=filled, fillcolor=red, fontcolor=white];"));
+        assert!(dot.contains("0 [style=filled, fillcolor=blue, fontcolor=white];"));
+        assert!(dot.contains("0 -> 1 [style=dotted];"));
+        assert!(dot.contains("0 -> 2 [color=blue, penwidth=2];"));
+        assert!(dot.contains("2 -> 3 [color=blue, penwidth=2];"));
+        assert!(dot.trim_end().ends_with('}'));
+    }
+
+    #[test]
+    fn export_graphml_csv() {
+        let g = graph::tests::graph_from(vec![vec![], vec![0], vec![1]]);
+        let mut out = Vec::new();
+        Exporter::new(&g)
+            .longest_path()
+            .to_graphml(&mut out)
+            .unwrap();
+        let xml = String::from_utf8(out).unwrap();
+        assert_eq!(xml.matches("<node id=").count(), 3);
+        assert!(
+            xml.contains(r#"<edge source="n1" target="n2"><data key="path">true</data></edge>"#)
+        );
+
+        let s = ExclusionSet::from_nodes(&g, vec![2]);
+        let mut out = Vec::new();
+        Exporter::new(&g).exclusion(&s).to_csv(&mut out).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        assert_eq!(
+            csv,
+            "parent,child,parent_excluded,child_excluded,path\n\
+             0,1,false,false,false\n\
+             1,2,false,true,false\n"
+        );
+    }
+
+    #[test]
+    fn export_node_index() {
+        // same output for a graph stored with u32 nodes
+        let spec = GraphSpec {
+            size: 64,
+            seed: graph::tests::TEST_SEED,
+            algo: DRGAlgo::MetaBucket(3),
+        };
+        let g = Graph::new_from_rng(spec, &mut ChaChaRng::from_seed(spec.seed)).unwrap();
+        let g32 = Graph::<u32>::generate(spec, &mut ChaChaRng::from_seed(spec.seed)).unwrap();
+        let s = ExclusionSet::from_nodes(&g, vec![3, 10, 42]);
+        let (mut dot, mut dot32) = (Vec::new(), Vec::new());
+        Exporter::new(&g)
+            .exclusion(&s)
+            .longest_path()
+            .to_dot(&mut dot)
+            .unwrap();
+        Exporter::new(&g32)
+            .exclusion(&s)
+            .longest_path()
+            .to_dot(&mut dot32)
+            .unwrap();
+        assert_eq!(dot32, dot);
+    }
+}
@@ -2,14 +2,22 @@ use fnv::FnvHasher;
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaChaRng;
 use rayon::prelude::*;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use sha2::Digest;
 use std::cmp::max;
 use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
 use std::error;
 use std::fmt;
 use std::fs::File;
 use std::hash::{BuildHasherDefault, Hash};
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use crate::error::Error;
+use crate::import::{self, Labelling};
 
 /// Data that completely specifies the `Graph` to be created. Many runs
 /// from the save stored data should produce the same `Graph` always
@@ -39,41 +47,178 @@ impl fmt::Display for GraphSpec {
             DRGAlgo::MetaBucket(d) => write!(f, "meta-bucket (degree {})),", d),
             DRGAlgo::KConnector(k) => write!(f, "{}-connect)", k),
             DRGAlgo::Ren21(d) => write!(f, "ren21(degree {})", d),
+            DRGAlgo::PowerLaw(d, e) => write!(f, "power-law(degree {}, exponent {})", d, e),
+            DRGAlgo::Imported => write!(f, "imported)"),
+            DRGAlgo::Custom(d) => write!(f, "custom(degree {}))", d),
         }
     }
 }
 // Graph holds the parameters and the edges of the graph. This is a special type
 // of graph that has a *proper* labelling: for each edge (i,j), we have i < j.
+// The nodes are stored with the index type `I` (see `NodeIndex`), e.g., a
+// `Graph<u32>` takes half the memory of the default one for graphs of up to
+// 2^32 nodes. The graphs built from others (e.g., the indegree reduction) keep
+// the index type and fail with `Error::IndexOverflow` if they don't fit in it.
 #[derive(Debug, Serialize, Deserialize)]
-pub struct Graph {
+pub struct Graph<I = Node> {
     // parents holds all parents relationships of all nodes.
     // If j = parents[i][u] (for any u), then there is an edge (j -> i),
     // i.e. j is the parent of i.
     // The capacity of the graph is the size of the vector - Some nodes may be
     // absent when counted, i.e. node i may not have any parent and may not be
     // the parent of any other node. In that case, it is not included in the graph G
-    parents: Vec<Vec<Node>>,
+    parents: Vec<Vec<I>>,
     // FIXME: Use slices, after construction this doesn't change.
     spec: GraphSpec,
 
     // children holds all the children relationships of all nodes.
     // If j = children[i][u] for any u, then there is an edge (i -> j).
-    // NOTE: it is NOT computed by default, only when calling children_project()
-    children: Vec<Vec<usize>>,
+    // NOTE: it is NOT computed by default, only the first time `children()`
+    // is called (from any thread).
+    #[serde(skip)]
+    children: OnceLock<Vec<Vec<I>>>,
+
+    // collapsed[i] is the number of nodes that lost `i` of their sampled
+    // parents when removing duplicates at generation time (only recorded
+    // for generated graphs).
+    #[serde(default)]
+    collapsed: Vec<usize>,
 }
 
+/// Position of a node in its graph, used to address it in the APIs (and in
+/// the per-node arrays of the attacks) whatever the `NodeIndex` the graph
+/// stores it with.
 pub type Node = usize;
 
+/// Integer type the nodes of a `Graph` (and its `Edge`s) are stored with. The
+/// graph can only have as many nodes as the type can index, which is checked
+/// when it's created.
+pub trait NodeIndex:
+    Copy
+    + Ord
+    + Hash
+    + fmt::Debug
+    + fmt::Display
+    + Default
+    + Send
+    + Sync
+    + Serialize
+    + DeserializeOwned
+    + 'static
+{
+    /// Number of bits of the type, the msbd of the edges is always below it.
+    const BITS: usize;
+
+    /// Converts the position of a node, panicking if it doesn't fit (only
+    /// used for the nodes of a graph whose size was checked with `fits`).
+    fn from_node(node: Node) -> Self;
+
+    /// Converts the position of a node, `None` if it doesn't fit.
+    fn try_from_node(node: Node) -> Option<Self>;
+
+    /// Position of the node.
+    fn node(self) -> Node;
+
+    /// Whether a graph of `size` nodes can be indexed with this type.
+    fn fits(size: usize) -> bool {
+        size == 0 || Self::try_from_node(size - 1).is_some()
+    }
+}
+
+macro_rules! node_index {
+    ($($t:ty),*) => {$(
+        impl NodeIndex for $t {
+            const BITS: usize = <$t>::BITS as usize;
+
+            fn from_node(node: Node) -> Self {
+                Self::try_from_node(node)
+                    .unwrap_or_else(|| panic!("node {} overflows {}", node, stringify!($t)))
+            }
+
+            fn try_from_node(node: Node) -> Option<Self> {
+                <$t>::try_from(node).ok()
+            }
+
+            fn node(self) -> Node {
+                Node::try_from(self)
+                    .unwrap_or_else(|_| panic!("{} {} overflows usize", stringify!($t), self))
+            }
+        }
+    )*};
+}
+
+node_index!(u32, u64, usize);
+
+/// Index type to store the nodes of the graphs with when it's picked at
+/// runtime (see `NodeIndex`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IndexWidth {
+    /// `u32` if the graph fits in it, `usize` otherwise.
+    Auto,
+    U32,
+    Usize,
+}
+
+impl IndexWidth {
+    /// Whether the nodes of a graph of `size` nodes are stored as `u32`.
+    pub fn is_u32(self, size: usize) -> bool {
+        match self {
+            IndexWidth::Auto => u32::fits(size),
+            IndexWidth::U32 => true,
+            IndexWidth::Usize => false,
+        }
+    }
+}
+
+/// Structural statistics of a `Graph`. The histograms are indexed by the
+/// degree, e.g., `in_degree[i]` is the number of nodes with `i` parents.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphStats {
+    pub size: usize,
+    pub edges: usize,
+    pub depth: usize,
+    pub in_degree: Vec<usize>,
+    pub out_degree: Vec<usize>,
+    /// Edges per length bucket (see `Graph::buckets`).
+    pub edge_lengths: Vec<usize>,
+    /// Nodes per number of parents collapsed when removing duplicates at
+    /// generation time (empty if unknown, e.g., for imported graphs).
+    pub collapsed: Vec<usize>,
+}
+
+impl fmt::Display for GraphStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "graph stats: size={}, edges={}, depth={}",
+            self.size, self.edges, self.depth
+        )?;
+        let histograms = [
+            ("in-degree", &self.in_degree),
+            ("out-degree", &self.out_degree),
+            ("edge length <= 2^i", &self.edge_lengths),
+            ("collapsed parents", &self.collapsed),
+        ];
+        for (name, histogram) in histograms.iter() {
+            writeln!(f, "{}:", name)?;
+            for (i, count) in histogram.iter().enumerate().filter(|&(_, &c)| c > 0) {
+                writeln!(f, "\t{:>4}: {}", i, count)?;
+            }
+        }
+        Ok(())
+    }
+}
+
 /// An edge represented as a parent-child relation (an expansion of the short
 /// `(u,v)` notation used in the paper).
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
-pub struct Edge {
-    pub parent: Node,
-    pub child: Node,
+pub struct Edge<I = Node> {
+    pub parent: I,
+    pub child: I,
 }
 
-impl Edge {
-    pub fn new(parent: Node, child: Node) -> Edge {
+impl<I: NodeIndex> Edge<I> {
+    pub fn new(parent: I, child: I) -> Edge<I> {
         debug_assert!(
             parent < child,
             "the parent {} is not smaller than child {}",
@@ -92,9 +237,67 @@ pub type FastHashSet<T> = HashSet<T, BuildHasherDefault<FnvHasher>>;
 pub type NodeSet = FastHashSet<Node>;
 pub type EdgeSet = FastHashSet<Edge>;
 
+/// Set of nodes of a graph of known size that can be cleared in O(1), to be
+/// reused across the iterations of an attack without reallocating: each node
+/// is stamped with the epoch it was inserted in, and clearing starts a new
+/// epoch (the stamps are only reset when the epoch counter wraps around).
+#[derive(Debug, Clone)]
+pub struct StampedNodeSet {
+    stamps: Vec<u32>,
+    epoch: u32,
+    // nodes inserted in the current epoch, in insertion order
+    nodes: Vec<Node>,
+}
+
+impl StampedNodeSet {
+    pub fn new(size: usize) -> Self {
+        StampedNodeSet {
+            stamps: vec![0; size],
+            epoch: 1,
+            nodes: Vec::new(),
+        }
+    }
+
+    pub fn contains(&self, node: Node) -> bool {
+        self.stamps[node] == self.epoch
+    }
+
+    /// Returns whether the node wasn't already in the set.
+    pub fn insert(&mut self, node: Node) -> bool {
+        if self.contains(node) {
+            return false;
+        }
+        self.stamps[node] = self.epoch;
+        self.nodes.push(node);
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.epoch = self.epoch.wrapping_add(1);
+        if self.epoch == 0 {
+            self.stamps.iter_mut().for_each(|stamp| *stamp = 0);
+            self.epoch = 1;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Nodes in the set in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = Node> + '_ {
+        self.nodes.iter().cloned()
+    }
+}
+
 // DRGAlgo represents which algorithm can be used to create the edges so a Graph is
 // a Depth Robust Graph
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub enum DRGAlgo {
     // BucketSample is the regular bucket sampling algorithm with degree 2
     BucketSample,
@@ -116,6 +319,16 @@ pub enum DRGAlgo {
     ///    end
     ///
     Ren21(usize),
+    /// Each node (besides its direct predecessor) gets `degree - 1` parents at
+    /// a distance `d` sampled with probability proportional to `d^-exponent`.
+    PowerLaw(usize, f64),
+    /// Graph imported from an external description (see the `import` module),
+    /// it can't be generated again from its `GraphSpec`.
+    Imported,
+    /// Graph of the given degree generated by a custom `ParentSampler` (see
+    /// `Graph::new_with_sampler`), it can't be generated again from its
+    /// `GraphSpec` either.
+    Custom(usize),
 }
 
 /// Range used for a uniform distribution sample in `Rng::gen_range`: `[low, high)`.
@@ -150,29 +363,144 @@ where
     }
 }
 
+/// Distribution of the distance between a node and each of its parents (other
+/// than the direct predecessor), the only thing that changes between the
+/// DRSample-like constructions. New distributions only need to implement this
+/// to be generated through `Graph::new_with_sampler`.
+pub trait ParentSampler: Sync {
+    /// Number of parents of each node, including the direct predecessor.
+    fn degree(&self) -> usize;
+
+    /// Sample the distance `d` in `[1, node]` to one parent of `node` (always
+    /// called with `node >= 2`), that parent being `node - d`.
+    fn back_distance(&self, node: Node, rng: &mut ChaChaRng) -> usize;
+
+    /// Construction recorded in the spec of the graphs of the sampler,
+    /// `Custom` for the ones that aren't built in.
+    fn algo(&self) -> DRGAlgo {
+        DRGAlgo::Custom(self.degree())
+    }
+}
+
+/// Implementation of the meta-graph construction algorithm described in page 22
+/// of the porep paper https://web.stanford.edu/~bfisch/porep_short.pdf
+/// It produces a degree-d graph on average.
+pub struct MetaBucketSampler {
+    pub degree: usize,
+}
+
+impl ParentSampler for MetaBucketSampler {
+    fn degree(&self) -> usize {
+        self.degree
+    }
+
+    fn algo(&self) -> DRGAlgo {
+        DRGAlgo::MetaBucket(self.degree)
+    }
+
+    fn back_distance(&self, node: Node, rng: &mut ChaChaRng) -> usize {
+        // similar to bucket_sample but we select m parents instead
+        // of just one
+        node - Graph::sample_parent_node(node, self.degree - 1, rng).0
+    }
+}
+
+/// Sampling of `Ren21`: the distance is `floor(2^k)` with `k` uniform in `[0, log v)`.
+pub struct Ren21Sampler {
+    pub degree: usize,
+}
+
+impl ParentSampler for Ren21Sampler {
+    fn degree(&self) -> usize {
+        self.degree
+    }
+
+    fn algo(&self) -> DRGAlgo {
+        DRGAlgo::Ren21(self.degree)
+    }
+
+    fn back_distance(&self, node: Node, rng: &mut ChaChaRng) -> usize {
+        let log_node = (node as f64).log2();
+        // random [0,log(v) [
+        let k = rng.gen_range(0 as f64, log_node);
+        // Edge( v - floor(2^k), v)
+        let twokf = k.exp2().floor() as usize;
+        assert!(twokf < node);
+        twokf
+    }
+}
+
+/// Sampling of `PowerLaw`, using the inverse of the CDF of the continuous
+/// distribution (truncated to `[1, node]`) and flooring the result.
+pub struct PowerLawSampler {
+    pub degree: usize,
+    pub exponent: f64,
+}
+
+impl ParentSampler for PowerLawSampler {
+    fn degree(&self) -> usize {
+        self.degree
+    }
+
+    fn algo(&self) -> DRGAlgo {
+        DRGAlgo::PowerLaw(self.degree, self.exponent)
+    }
+
+    fn back_distance(&self, node: Node, rng: &mut ChaChaRng) -> usize {
+        let u: f64 = rng.gen();
+        let max = node as f64;
+        let distance = if (self.exponent - 1.0).abs() < f64::EPSILON {
+            max.powf(u)
+        } else {
+            let e = 1.0 - self.exponent;
+            ((max.powf(e) - 1.0) * u + 1.0).powf(1.0 / e)
+        };
+        // clamp to guard against rounding at the bounds
+        std::cmp::min(std::cmp::max(distance.floor() as usize, 1), node)
+    }
+}
+
 /// Exclusion set `S` of nodes that are removed from `G`. Encapsulated in this
 /// interface to evaluate optimizations to its implementation (e.g., set vs vec).
-#[derive(Debug, Clone, PartialEq)]
+/// Backed by a bitset of one bit per node of `G`, it serializes to the runs of
+/// excluded and non-excluded nodes (see `runs`), compact for the structured
+/// sets the attacks output. Nodes are addressed by their `Node` position, so
+/// the same set applies to a graph whatever its `NodeIndex`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(into = "ExclusionRuns", try_from = "ExclusionRuns")]
 pub struct ExclusionSet {
-    v: Vec<bool>,
+    // bit `i % 64` of word `i / 64` is set iff node `i` is in the set, the bits
+    // beyond `len` are always unset
+    words: Vec<u64>,
+    // number of nodes of the graph
+    len: usize,
+    // number of nodes in the set, kept up to date by `insert` and `remove`
+    // and recounted after the set operations
     size: usize,
 }
 
+const WORD_BITS: usize = 64;
+
 impl ExclusionSet {
+    /// Largest `len()` of a deserialized set (2^40 nodes, a 128 GiB bitset),
+    /// so corrupt inputs can't request arbitrary allocations.
+    pub const MAX_LEN: usize = 1 << 40;
+
     /// Create new set `S` for a specified `size`.
     pub fn new_with_size(size: usize) -> Self {
         ExclusionSet {
-            v: vec![false; size],
+            words: vec![0; size.div_ceil(WORD_BITS)],
+            len: size,
             size: 0,
         }
     }
 
     /// Create new set `S` for a specified `G`.
-    pub fn new(graph: &Graph) -> Self {
+    pub fn new<I: NodeIndex>(graph: &Graph<I>) -> Self {
         Self::new_with_size(graph.size())
     }
 
-    pub fn from_nodes(graph: &Graph, nodes: Vec<Node>) -> Self {
+    pub fn from_nodes<I: NodeIndex>(graph: &Graph<I>, nodes: Vec<Node>) -> Self {
         let mut es = Self::new(graph);
         for node in nodes {
             es.insert(node);
@@ -181,38 +509,270 @@ impl ExclusionSet {
     }
 
     pub fn contains(&self, node: Node) -> bool {
-        self.v[node]
+        // the last word has room for nodes beyond `len` that would be
+        // silently counted in `size`
+        assert!(node < self.len, "node {} out of the set range", node);
+        self.words[node / WORD_BITS] & (1 << (node % WORD_BITS)) != 0
     }
 
     pub fn insert(&mut self, node: Node) {
         if !self.contains(node) {
-            self.v[node] = true;
+            self.words[node / WORD_BITS] |= 1 << (node % WORD_BITS);
             self.size += 1;
         }
     }
 
+    pub fn remove(&mut self, node: Node) {
+        if self.contains(node) {
+            self.words[node / WORD_BITS] &= !(1 << (node % WORD_BITS));
+            self.size -= 1;
+        }
+    }
+
     pub fn size(&self) -> usize {
         self.size
     }
 
+    /// Number of nodes the set ranges over (the size of its graph).
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Nodes in the set in increasing order.
+    pub fn iter(&self) -> impl Iterator<Item = Node> + '_ {
+        self.words.iter().enumerate().flat_map(|(i, &word)| {
+            let mut word = word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(i * WORD_BITS + bit)
+            })
+        })
+    }
+
     pub fn extend(&mut self, es: &ExclusionSet) {
+        self.combine(es, |a, b| a | b);
+    }
+
+    /// Keep only the nodes also in `es`.
+    pub fn retain(&mut self, es: &ExclusionSet) {
+        self.combine(es, |a, b| a & b);
+    }
+
+    /// Remove the nodes in `es`.
+    pub fn subtract(&mut self, es: &ExclusionSet) {
+        self.combine(es, |a, b| a & !b);
+    }
+
+    pub fn union(&self, es: &ExclusionSet) -> ExclusionSet {
+        let mut set = self.clone();
+        set.extend(es);
+        set
+    }
+
+    pub fn intersection(&self, es: &ExclusionSet) -> ExclusionSet {
+        let mut set = self.clone();
+        set.retain(es);
+        set
+    }
+
+    pub fn difference(&self, es: &ExclusionSet) -> ExclusionSet {
+        let mut set = self.clone();
+        set.subtract(es);
+        set
+    }
+
+    fn combine<F: Fn(u64, u64) -> u64>(&mut self, es: &ExclusionSet, op: F) {
         assert!(
-            self.v.len() == es.v.len(),
-            "exclusion set len mismatch when extending"
+            self.len == es.len,
+            "exclusion set len mismatch when combining"
         );
-        for node in 0..self.v.len() {
-            if !self.contains(node) && es.contains(node) {
-                self.v[node] = true;
-                self.size += 1;
+        self.words
+            .iter_mut()
+            .zip(es.words.iter())
+            .for_each(|(a, &b)| *a = op(*a, b));
+        self.size = self.words.iter().map(|w| w.count_ones() as usize).sum();
+    }
+
+    /// Lengths of the alternating runs of consecutive nodes out of and in the
+    /// set, starting with the nodes out of it (so the first run is empty if
+    /// node 0 is in the set). They add up to `len()`.
+    pub fn runs(&self) -> Vec<usize> {
+        let mut runs = Vec::new();
+        let mut current = 0;
+        let mut inside = false;
+        for node in 0..self.len {
+            if self.contains(node) != inside {
+                runs.push(current);
+                current = 0;
+                inside = !inside;
+            }
+            current += 1;
+        }
+        runs.push(current);
+        runs
+    }
+
+    /// Inverse of `runs`, failing instead of allocating if `len` is over
+    /// `MAX_LEN` or the memory for it isn't available.
+    pub fn from_runs(len: usize, runs: &[usize]) -> Result<Self, ExclusionSetError> {
+        let covered = runs.iter().fold(0usize, |acc, &r| acc.saturating_add(r));
+        if covered != len {
+            return Err(ExclusionSetError::RunsLength { len, covered });
+        }
+        if len > Self::MAX_LEN {
+            return Err(ExclusionSetError::TooLarge { len });
+        }
+        let mut words = Vec::new();
+        words
+            .try_reserve_exact(len.div_ceil(WORD_BITS))
+            .map_err(|_| ExclusionSetError::TooLarge { len })?;
+        words.resize(len.div_ceil(WORD_BITS), 0);
+        let mut es = ExclusionSet {
+            words,
+            len,
+            size: 0,
+        };
+        let mut start = 0;
+        for (i, &run) in runs.iter().enumerate() {
+            if i % 2 == 1 {
+                (start..start + run).for_each(|node| es.insert(node));
+            }
+            start += run;
+        }
+        Ok(es)
+    }
+
+    /// Binary encoding of the set: `len()`, the number of runs and the
+    /// `runs`, each as an LEB128 varint.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let runs = self.runs();
+        let mut bytes = Vec::with_capacity(runs.len() + 16);
+        write_varint(&mut bytes, self.len);
+        write_varint(&mut bytes, runs.len());
+        runs.iter().for_each(|&run| write_varint(&mut bytes, run));
+        bytes
+    }
+
+    /// Inverse of `to_bytes` for a set of the graph of `expected` nodes.
+    pub fn from_bytes(bytes: &[u8], expected: usize) -> Result<Self, ExclusionSetError> {
+        let mut bytes = bytes;
+        let mut next = || read_varint(&mut bytes).map_err(|_| ExclusionSetError::Truncated);
+        let len = next()?;
+        if len != expected {
+            return Err(ExclusionSetError::Len { expected, len });
+        }
+        let count = next()?;
+        // not preallocated from the (untrusted) count
+        let mut runs = Vec::new();
+        for _ in 0..count {
+            runs.push(next()?);
+        }
+        Self::from_runs(len, &runs)
+    }
+}
+
+pub(crate) fn write_varint(bytes: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        bytes.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+/// Inverse of `write_varint`, failing with `InvalidData` if the varint
+/// doesn't fit in a `usize`.
+pub(crate) fn read_varint<R: Read>(r: &mut R) -> io::Result<usize> {
+    let mut value = 0usize;
+    let mut byte = [0; 1];
+    for shift in (0..usize::BITS as usize).step_by(7) {
+        r.read_exact(&mut byte)?;
+        let payload = (byte[0] & 0x7f) as usize;
+        // the last byte only has room for the remaining bits of the value
+        if shift + 7 > usize::BITS as usize && payload >> (usize::BITS as usize - shift) != 0 {
+            break;
+        }
+        value |= payload << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "varint overflow",
+    ))
+}
+
+/// Serialized form of an `ExclusionSet`.
+#[derive(Serialize, Deserialize)]
+struct ExclusionRuns {
+    len: usize,
+    runs: Vec<usize>,
+}
+
+impl From<ExclusionSet> for ExclusionRuns {
+    fn from(es: ExclusionSet) -> Self {
+        ExclusionRuns {
+            len: es.len,
+            runs: es.runs(),
+        }
+    }
+}
+
+impl TryFrom<ExclusionRuns> for ExclusionSet {
+    type Error = ExclusionSetError;
+
+    fn try_from(runs: ExclusionRuns) -> Result<Self, Self::Error> {
+        ExclusionSet::from_runs(runs.len, &runs.runs)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExclusionSetError {
+    /// The runs don't cover exactly the nodes of the set.
+    RunsLength { len: usize, covered: usize },
+    /// The encoding ended in the middle of a number.
+    Truncated,
+    /// The set isn't for a graph of the `expected` size.
+    Len { expected: usize, len: usize },
+    /// The set is over `ExclusionSet::MAX_LEN` or can't be allocated.
+    TooLarge { len: usize },
+}
+
+impl fmt::Display for ExclusionSetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExclusionSetError::RunsLength { len, covered } => write!(
+                f,
+                "runs cover {} nodes instead of the {} of the set",
+                covered, len
+            ),
+            ExclusionSetError::Truncated => write!(f, "truncated exclusion set encoding"),
+            ExclusionSetError::Len { expected, len } => write!(
+                f,
+                "exclusion set of {} nodes for a graph of {}",
+                len, expected
+            ),
+            ExclusionSetError::TooLarge { len } => {
+                write!(f, "exclusion set of {} nodes is too large", len)
             }
         }
     }
 }
 
+impl error::Error for ExclusionSetError {}
+
 impl Graph {
     // new returns a new graph instance from the given parameters.
     // The graph's edges are not generated yet, call fill_drg to compute the edges.
-    pub fn new(size: usize, seed: [u8; 32], algo: DRGAlgo) -> Graph {
+    pub fn new(size: usize, seed: [u8; 32], algo: DRGAlgo) -> Result<Graph, Error> {
         // FIXME: To avoid changing too much code at the moment the `GraphSpec`
         //  is built here, but ideally the consumer should already provide it.
         let spec = GraphSpec { seed, size, algo };
@@ -222,19 +782,24 @@ impl Graph {
     }
 
     // FIXME: The RNG is not always necessary so this function is misleading.
-    pub fn new_from_rng(spec: GraphSpec, rng: &mut ChaChaRng) -> Graph {
-        let mut g = Graph {
-            spec,
-            parents: Vec::with_capacity(spec.size),
-            children: vec![],
-        };
-        match g.algo() {
-            DRGAlgo::BucketSample => g.bucket_sample(rng),
-            DRGAlgo::MetaBucket(degree) => g.meta_bucket(degree, rng),
-            DRGAlgo::KConnector(k) => g.connect_neighbors(k),
-            DRGAlgo::Ren21(degree) => g.ren21(degree),
-        }
-        g
+    pub fn new_from_rng(spec: GraphSpec, rng: &mut ChaChaRng) -> Result<Graph, Error> {
+        Self::generate(spec, rng)
+    }
+
+    /// Create a graph whose parents are generated from a custom `sampler`
+    /// (see `ParentSampler`). The `spec.algo` must be the one of the sampler
+    /// (`Custom` for the ones that aren't built in) so the spec recorded in
+    /// the graph doesn't claim another construction.
+    pub fn new_with_sampler<S: ParentSampler>(
+        spec: GraphSpec,
+        sampler: &S,
+    ) -> Result<Graph, Error> {
+        Self::generate_with_sampler(spec, sampler)
+    }
+
+    /// Same as `try_from_parents`, which can't fail with `usize` indexes.
+    pub(crate) fn from_parents(spec: GraphSpec, parents: Vec<Vec<Node>>) -> Graph {
+        Self::try_from_parents(spec, parents).expect("any size fits in usize")
     }
 
     /// load_or_create tries to read the json description of the graph specified
@@ -242,85 +807,51 @@ impl Graph {
     /// the rest of the argumetn to Graph::new, and saves the graph at the
     /// specified location.
     /// FIXME: why is it still taking so much time..
-    pub fn load_or_create(fname: &str, size: usize, seed: [u8; 32], algo: DRGAlgo) -> Graph {
+    pub fn load_or_create(
+        fname: &str,
+        size: usize,
+        seed: [u8; 32],
+        algo: DRGAlgo,
+    ) -> Result<Graph, Error> {
         if let Ok(graph) = Graph::load(fname) {
             println!("graph loaded from {}", fname);
             if graph.cap() == size {
-                return graph;
+                return Ok(graph);
             }
         }
-        let g = Graph::new(size, seed, algo);
-        g.save(fname);
+        let g = Graph::new(size, seed, algo)?;
+        g.save(fname)?;
         println!("graph created and saved at {}", fname);
-        g
+        Ok(g)
     }
 
-    fn load(fname: &str) -> Result<Graph, Box<dyn error::Error>> {
+    /// Read the json description of a graph saved with `save`, checking
+    /// that its parents are in range and smaller than their children (like
+    /// the graphs imported with `Labelling::Strict`).
+    pub fn load(fname: &str) -> Result<Graph, Error> {
         // Open the file in read-only mode with buffer.
         let file = File::open(fname)?;
 
-        let g = serde_json::from_reader(file)?;
+        let g: Graph = serde_json::from_reader(file)?;
+        if g.parents.len() != g.spec.size {
+            return Err(Error::InvalidSpec(format!(
+                "the spec has {} nodes but the graph {}",
+                g.spec.size,
+                g.parents.len()
+            )));
+        }
+        for (child, parents) in g.parents.iter().enumerate() {
+            for &parent in parents.iter() {
+                import::check_edge(parent, child, g.size(), Labelling::Strict)?;
+            }
+        }
         Ok(g)
     }
 
-    fn save(&self, fname: &str) {
-        let file =
-            File::create(fname).expect(format!("unable to save graph to {}", fname).as_str());
-
-        serde_json::to_writer(file, self)
-            .expect(format!("unable to save graph to {}", fname).as_str());
-    }
-
-    /// Number of nodes in the graph.
-    pub fn size(&self) -> usize {
-        self.spec.size
-    }
-
-    // depth_exclude returns the depth of the graph when excluding the given
-    // set of nodes
-    pub fn depth_exclude(&self, set: &ExclusionSet) -> usize {
-        self.parents
-            .iter()
-            .enumerate()
-            .fold(Vec::new(), |mut acc, (i, parents)| {
-                if set.contains(i) {
-                    // an excluded node has length 0
-                    acc.push(0);
-                    return acc;
-                }
-                match parents
-                    .iter()
-                    // dont take parent's length if contained in set
-                    .filter(|&p| !set.contains(*p))
-                    .map(|&p| acc[p] + 1)
-                    .max()
-                {
-                    // need the match because there might not be any values
-                    Some(depth) => acc.push(depth),
-                    None => acc.push(0),
-                }
-                acc
-            })
-            .into_iter()
-            .max()
-            .unwrap()
-    }
-
-    // depth returns the longest depth found in the graph
-    pub fn depth(&self) -> usize {
-        self.parents
-            .iter()
-            .fold(Vec::new(), |mut acc, parents| {
-                // take the depth of each parents + 1 then take the max of it
-                match parents.iter().map(|&p| acc[p] + 1).max() {
-                    Some(depth) => acc.push(depth),
-                    None => acc.push(0),
-                };
-                acc
-            })
-            .into_iter()
-            .max()
-            .unwrap()
+    pub fn save(&self, fname: &str) -> Result<(), Error> {
+        let file = File::create(fname)?;
+        serde_json::to_writer(file, self)?;
+        Ok(())
     }
 
     /// Returns the depth of the graph when removing the given edges
@@ -355,128 +886,77 @@ impl Graph {
             })
             .into_iter()
             .max()
-            .unwrap()
+            .unwrap_or(0)
     }
 
-    // remove returns a new graph with the specified nodes removed
-    // TODO slow path checking in O(n) - consider using bitset for nodes
-    pub fn remove(&self, nodes: &ExclusionSet) -> Graph {
-        let mut out = Vec::with_capacity(self.parents.len());
-        for i in 0..self.parents.len() {
-            let parents = self.parents.get(i).unwrap();
-            let new_parents = if nodes.contains(i) {
-                // no parent for a deleted node
-                Vec::new()
-            } else {
-                // only take parents which are not in the list of nodes
-                parents
-                    .into_iter()
-                    .filter(|&parent| !nodes.contains(*parent))
-                    .map(|&p| p)
-                    .collect::<Vec<usize>>()
-            };
-            out.push(new_parents);
-        }
-
-        Graph {
-            spec: GraphSpec {
-                size: (&out).len(),
-                ..self.spec
-            },
-            // FIXME: We should think if we actually need to create new graphs
-            //  out of old ones (just to count the depth of a reduced set).
-            //  This violates the contract of `GraphSpec`: this new graph
-            //  wouldn't be able to be recreated from it.
-            parents: out,
-            children: vec![],
-        }
-    }
-
-    fn algo(&self) -> DRGAlgo {
-        self.spec.algo
-    }
-
-    // Implementation of the first algorithm BucketSample on page 22 of the
-    // porep paper : https://web.stanford.edu/~bfisch/porep_short.pdf
-    // It produces a degree-2 graph which is asymptotically depth-robust.
-    fn bucket_sample(&mut self, rng: &mut ChaChaRng) {
-        for node in 0..self.parents.capacity() {
-            let mut parents = Vec::new();
-            match node {
-                // no parents for the first node
-                0 => {}
-                // second node only has the first node as parent
-                1 => {
-                    parents.push(0);
-                }
-                _ => {
-                    // push the direct parent of i, i.e. (i-1 -> i)
-                    parents.push(node - 1);
-
-                    // choose a bucket index
-                    let max_bucket = (node as f32).log2().ceil() as usize;
-                    let i: usize = rng.gen_range(1, max_bucket + 1);
-                    // get a node from that bucket, i.e. from [2^i-1, 2^i[
-                    // exclusif because otherwise a parent can be the same
-                    // as its child
-                    let max = std::cmp::min(node, 1 << i);
-                    let min = std::cmp::max(2, max >> 1);
-                    let random_parent = node - rng.gen_range(min, max + 1);
-                    assert!(random_parent < node);
-                    parents.push(random_parent);
-                }
+    /// Parents of `node` in `BucketSample` (possibly repeated), drawn from an
+    /// `rng` shared by all the nodes in order.
+    pub(crate) fn bucket_sample_parents(node: Node, rng: &mut ChaChaRng) -> Vec<Node> {
+        let mut parents = Vec::new();
+        match node {
+            // no parents for the first node
+            0 => {}
+            // second node only has the first node as parent
+            1 => {
+                parents.push(0);
+            }
+            _ => {
+                // push the direct parent of i, i.e. (i-1 -> i)
+                parents.push(node - 1);
+
+                // choose a bucket index
+                let max_bucket = max_bucket(node);
+                let i: usize = rng.gen_range(1, max_bucket + 1);
+                // get a node from that bucket, i.e. from [2^i-1, 2^i[
+                // exclusif because otherwise a parent can be the same
+                // as its child
+                let max = std::cmp::min(node, 1 << i);
+                let min = std::cmp::max(2, max >> 1);
+                let random_parent = node - rng.gen_range(min, max + 1);
+                assert!(random_parent < node);
+                parents.push(random_parent);
             }
-
-            remove_duplicate(&mut parents);
-            self.parents.push(parents);
         }
+        parents
     }
 
-    // Implementation of the meta-graph construction algorithm described in page 22
-    // of the porep paper https://web.stanford.edu/~bfisch/porep_short.pdf
-    // It produces a degree-d graph on average.
-    fn meta_bucket(&mut self, degree: usize, _: &mut ChaChaRng) {
-        let m = degree - 1;
-        self.parents = (0..self.parents.capacity())
-            .into_par_iter()
-            .map(|node| {
-                let mut parents = match node {
-                    // no parents for the first node
-                    0 => vec![],
-                    // second node only has the first node as parent
-                    1 => {
-                        vec![0]
-                    }
-                    _ => {
-                        (0..degree)
-                            .into_par_iter()
-                            .map(|parent_idx| {
-                                if parent_idx == 0 {
-                                    // push the direct parent of i, i.e. (i-1 -> i)
-                                    return node - 1;
-                                }
-                                //let mut d = Sha256::new();
-                                let mut d = blake3::Hasher::new();
-                                d.update(&self.spec.seed);
-                                d.update(&node.to_be_bytes()[..]);
-                                d.update(&parent_idx.to_be_bytes()[..]);
-                                let digest = d.finalize();
-                                let mut local_seed: [u8; 32] = [0; 32];
-                                local_seed.copy_from_slice(&digest[..]);
-                                let mut local_rng = ChaChaRng::from_seed(local_seed);
-
-                                // similar to bucket_sample but we select m parents instead
-                                // of just one
-                                Self::sample_parent_node(node, m, &mut local_rng).0
-                            })
-                            .collect::<Vec<_>>()
+    /// Parents of `node` generated by `sampler` (possibly repeated), see
+    /// `sample_parents`.
+    pub(crate) fn sampled_parents<S: ParentSampler>(
+        seed: &[u8; 32],
+        node: Node,
+        sampler: &S,
+    ) -> Vec<Node> {
+        match node {
+            // no parents for the first node
+            0 => vec![],
+            // second node only has the first node as parent
+            1 => {
+                vec![0]
+            }
+            _ => (0..sampler.degree())
+                .into_par_iter()
+                .map(|parent_idx| {
+                    if parent_idx == 0 {
+                        // push the direct parent of i, i.e. (i-1 -> i)
+                        return node - 1;
                     }
-                };
-                // filtering duplicate parents
-                remove_duplicate(&mut parents);
-                parents
-            })
-            .collect::<Vec<_>>();
+                    //let mut d = Sha256::new();
+                    let mut d = blake3::Hasher::new();
+                    d.update(seed);
+                    d.update(&node.to_be_bytes()[..]);
+                    d.update(&parent_idx.to_be_bytes()[..]);
+                    let digest = d.finalize();
+                    let mut local_seed: [u8; 32] = [0; 32];
+                    local_seed.copy_from_slice(&digest[..]);
+                    let mut local_rng = ChaChaRng::from_seed(local_seed);
+
+                    let distance = sampler.back_distance(node, &mut local_rng);
+                    assert!(distance > 0 && distance <= node);
+                    node - distance
+                })
+                .collect::<Vec<_>>(),
+        }
     }
 
     /// Core of the meta-graph construction (`meta_bucket`) isolated for audit and
@@ -502,7 +982,7 @@ impl Graph {
         // with each "bucket" of node having length
         let meta_idx = node * m;
         // ceil instead of floor() + 1
-        let max_bucket = (meta_idx as f32).log2().ceil() as usize;
+        let max_bucket = max_bucket(meta_idx);
         // choose bucket index {1 ... ceil(log2(idx))}
         let bucket_range = UniformSampleRange {
             low: 1,
@@ -534,96 +1014,359 @@ impl Graph {
             },
         )
     }
+}
 
-    fn ren21(&mut self, degree: usize) {
-        self.parents = (0..self.parents.capacity())
-            .into_par_iter()
-            .map(|node| {
-                let log_node = (node as f64).log2();
-                let mut parents = match node {
-                    0 => vec![],
-                    1 => vec![0],
-                    _ => {
-                        (0..degree)
-                            .into_par_iter()
-                            .map(|parent_idx| {
-                                if parent_idx == 0 {
-                                    // include parent
-                                    return node - 1;
-                                }
-                                //let mut d = Sha256::new();
-                                let mut d = blake3::Hasher::new();
-                                d.update(&self.spec.seed);
-                                d.update(&node.to_be_bytes()[..]);
-                                d.update(&parent_idx.to_be_bytes()[..]);
-                                let digest = d.finalize();
-                                let mut local_seed: [u8; 32] = [0; 32];
-                                local_seed.copy_from_slice(&digest[..]);
-                                let mut local_rng = ChaChaRng::from_seed(local_seed);
-                                // random [0,log(v) [
-                                let k = local_rng.gen_range(0 as f64, log_node);
-                                // Edge( v - floor(2^k), v)
-                                let twok = k.exp2();
-                                let twokf = twok.floor() as usize;
-                                assert!(twokf < node);
-                                node - twokf
-                            })
-                            .collect::<Vec<_>>()
-                    }
+impl<I: NodeIndex> Graph<I> {
+    /// Same as `Graph::new_from_rng` storing the nodes with the index type
+    /// `I`, e.g., `Graph::<u32>::generate(spec, &mut rng)`. Fails if the
+    /// nodes of the graph don't fit in `I`.
+    pub fn generate(spec: GraphSpec, rng: &mut ChaChaRng) -> Result<Self, Error> {
+        let mut g = Self::empty(spec)?;
+        match g.algo() {
+            DRGAlgo::BucketSample => g.bucket_sample(rng),
+            DRGAlgo::MetaBucket(degree) => g.sample_parents(&MetaBucketSampler { degree }),
+            DRGAlgo::KConnector(k) => g.connect_neighbors(k)?,
+            DRGAlgo::Ren21(degree) => g.sample_parents(&Ren21Sampler { degree }),
+            DRGAlgo::PowerLaw(degree, exponent) => {
+                g.sample_parents(&PowerLawSampler { degree, exponent })
+            }
+            algo @ DRGAlgo::Imported | algo @ DRGAlgo::Custom(_) => {
+                return Err(Error::Unsupported(algo))
+            }
+        }
+        Ok(g)
+    }
+
+    /// Same as `Graph::new_with_sampler` storing the nodes with the index
+    /// type `I`.
+    pub fn generate_with_sampler<S: ParentSampler>(
+        spec: GraphSpec,
+        sampler: &S,
+    ) -> Result<Self, Error> {
+        if spec.algo != sampler.algo() {
+            return Err(Error::InvalidSpec(format!(
+                "the spec is of {:?} graphs but the sampler generates {:?} ones",
+                spec.algo,
+                sampler.algo()
+            )));
+        }
+        let mut g = Self::empty(spec)?;
+        g.sample_parents(sampler);
+        Ok(g)
+    }
+
+    fn empty(spec: GraphSpec) -> Result<Self, Error> {
+        if !I::fits(spec.size) {
+            return Err(Error::IndexOverflow {
+                size: spec.size,
+                bits: I::BITS,
+            });
+        }
+        Ok(Graph {
+            spec,
+            parents: Vec::with_capacity(spec.size),
+            children: OnceLock::new(),
+            collapsed: vec![],
+        })
+    }
+
+    /// Build a new graph out of the given parents relationships, keeping the
+    /// rest of the `GraphSpec` of this one (used by graph transformations).
+    /// Fails with `Error::IndexOverflow` if the new graph doesn't fit in `I`.
+    // FIXME: Same as `remove`, the new graph can't be recreated from its spec.
+    pub(crate) fn derive(&self, parents: Vec<Vec<Node>>) -> Result<Self, Error> {
+        Self::try_from_parents(self.spec, parents)
+    }
+
+    /// Build a graph from already validated parents relationships (they must
+    /// follow the proper labelling), only the `size` of the `spec` is replaced.
+    /// Fails with `Error::IndexOverflow` if the graph doesn't fit in `I`.
+    pub(crate) fn try_from_parents(
+        spec: GraphSpec,
+        parents: Vec<Vec<Node>>,
+    ) -> Result<Self, Error> {
+        if !I::fits(parents.len()) {
+            return Err(Error::IndexOverflow {
+                size: parents.len(),
+                bits: I::BITS,
+            });
+        }
+        Ok(Graph {
+            spec: GraphSpec {
+                size: parents.len(),
+                ..spec
+            },
+            // the parents are below their child so they also fit
+            parents: parents.into_iter().map(to_index).collect(),
+            children: OnceLock::new(),
+            collapsed: vec![],
+        })
+    }
+
+    /// Number of nodes in the graph.
+    pub fn size(&self) -> usize {
+        self.spec.size
+    }
+
+    // depth_exclude returns the depth of the graph when excluding the given
+    // set of nodes
+    pub fn depth_exclude(&self, set: &ExclusionSet) -> usize {
+        self.parents
+            .iter()
+            .enumerate()
+            .fold(Vec::new(), |mut acc, (i, parents)| {
+                if set.contains(i) {
+                    // an excluded node has length 0
+                    acc.push(0);
+                    return acc;
+                }
+                match parents
+                    .iter()
+                    .map(|p| p.node())
+                    // dont take parent's length if contained in set
+                    .filter(|&p| !set.contains(p))
+                    .map(|p| acc[p] + 1)
+                    .max()
+                {
+                    // need the match because there might not be any values
+                    Some(depth) => acc.push(depth),
+                    None => acc.push(0),
+                }
+                acc
+            })
+            .into_iter()
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns one of the longest paths of G-S (as the list of its nodes in
+    /// order), whose length is `depth_exclude(set)`. Empty if all the nodes
+    /// are excluded.
+    pub fn longest_path_exclude(&self, set: &ExclusionSet) -> Vec<Node> {
+        // depth of each node along with the parent it was reached from
+        let mut depths: Vec<(usize, Option<Node>)> = Vec::with_capacity(self.size());
+        for (node, parents) in self.parents.iter().enumerate() {
+            let best = if set.contains(node) {
+                None
+            } else {
+                parents
+                    .iter()
+                    .map(|p| p.node())
+                    .filter(|&p| !set.contains(p))
+                    .max_by_key(|&p| depths[p].0)
+            };
+            depths.push(match best {
+                Some(p) => (depths[p].0 + 1, Some(p)),
+                None => (0, None),
+            });
+        }
+
+        let mut current = (0..self.size())
+            .filter(|&node| !set.contains(node))
+            .max_by_key(|&node| depths[node].0);
+        let mut path = Vec::new();
+        while let Some(node) = current {
+            path.push(node);
+            current = depths[node].1;
+        }
+        path.reverse();
+        path
+    }
+
+    /// Returns for each node of G-S the length of the longest path ending in
+    /// it (forward depth) and starting from it (backward depth), both 0 for
+    /// excluded nodes. A node lies on a longest path of G-S iff the sum of
+    /// its depths is `depth_exclude(set)`.
+    pub fn depths_exclude(&self, set: &ExclusionSet) -> (Vec<usize>, Vec<usize>) {
+        let mut forward = vec![0; self.size()];
+        let mut backward = vec![0; self.size()];
+        for (node, parents) in self.parents.iter().enumerate() {
+            if set.contains(node) {
+                continue;
+            }
+            forward[node] = parents
+                .iter()
+                .map(|p| p.node())
+                .filter(|&p| !set.contains(p))
+                .map(|p| forward[p] + 1)
+                .max()
+                .unwrap_or(0);
+        }
+        for (node, parents) in self.parents.iter().enumerate().rev() {
+            if set.contains(node) {
+                continue;
+            }
+            for p in parents
+                .iter()
+                .map(|p| p.node())
+                .filter(|&p| !set.contains(p))
+            {
+                backward[p] = std::cmp::max(backward[p], backward[node] + 1);
+            }
+        }
+        (forward, backward)
+    }
+
+    // depth returns the longest depth found in the graph
+    pub fn depth(&self) -> usize {
+        self.parents
+            .iter()
+            .fold(Vec::new(), |mut acc, parents| {
+                // take the depth of each parents + 1 then take the max of it
+                match parents.iter().map(|p| acc[p.node()] + 1).max() {
+                    Some(depth) => acc.push(depth),
+                    None => acc.push(0),
                 };
-                remove_duplicate(&mut parents);
+                acc
+            })
+            .into_iter()
+            .max()
+            .unwrap_or(0)
+    }
+
+    // remove returns a new graph with the specified nodes removed
+    // TODO slow path checking in O(n) - consider using bitset for nodes
+    pub fn remove(&self, nodes: &ExclusionSet) -> Graph<I> {
+        let mut out = Vec::with_capacity(self.parents.len());
+        for i in 0..self.parents.len() {
+            let parents = self.parents.get(i).unwrap();
+            let new_parents = if nodes.contains(i) {
+                // no parent for a deleted node
+                Vec::new()
+            } else {
+                // only take parents which are not in the list of nodes
                 parents
+                    .into_iter()
+                    .filter(|&parent| !nodes.contains(parent.node()))
+                    .map(|&p| p)
+                    .collect::<Vec<I>>()
+            };
+            out.push(new_parents);
+        }
+
+        Graph {
+            spec: GraphSpec {
+                size: (&out).len(),
+                ..self.spec
+            },
+            // FIXME: We should think if we actually need to create new graphs
+            //  out of old ones (just to count the depth of a reduced set).
+            //  This violates the contract of `GraphSpec`: this new graph
+            //  wouldn't be able to be recreated from it.
+            parents: out,
+            children: OnceLock::new(),
+            collapsed: vec![],
+        }
+    }
+
+    fn algo(&self) -> DRGAlgo {
+        self.spec.algo
+    }
+
+    // Implementation of the first algorithm BucketSample on page 22 of the
+    // porep paper : https://web.stanford.edu/~bfisch/porep_short.pdf
+    // It produces a degree-2 graph which is asymptotically depth-robust.
+    fn bucket_sample(&mut self, rng: &mut ChaChaRng) {
+        for node in 0..self.parents.capacity() {
+            let mut parents = Graph::bucket_sample_parents(node, rng);
+            let removed = remove_duplicate(&mut parents);
+            histogram_add(&mut self.collapsed, removed);
+            self.parents.push(to_index(parents));
+        }
+    }
+
+    /// Generate the parents of every node with the given `sampler`: the
+    /// first parent is always the direct predecessor and the rest are sampled
+    /// with an RNG seeded from the `spec` seed, the node and the parent index,
+    /// so the graph doesn't depend on the order in which nodes are generated.
+    fn sample_parents<S: ParentSampler>(&mut self, sampler: &S) {
+        let seed = self.spec.seed;
+        let parents = (0..self.parents.capacity())
+            .into_par_iter()
+            .map(|node| {
+                let mut parents = Graph::sampled_parents(&seed, node, sampler);
+                // filtering duplicate parents
+                let removed = remove_duplicate(&mut parents);
+                (to_index(parents), removed)
             })
             .collect::<Vec<_>>();
+        self.parents = Vec::with_capacity(parents.len());
+        for (node_parents, removed) in parents {
+            self.parents.push(node_parents);
+            histogram_add(&mut self.collapsed, removed);
+        }
     }
 
     /// Connect to `k` closest neighbors (see `KConnector`).
-    fn connect_neighbors(&mut self, k: usize) {
+    fn connect_neighbors(&mut self, k: usize) -> Result<(), Error> {
         // FIXME: Let the algorithms initialize the slices instead of working
         //  only with vectors.
         // let parents = vec![vec![]; self.size()];
         // FIXME: How to set the capacity for the inner empty vector to `k`?
 
-        debug_assert!(k > 0, "k {} is too small", k);
+        if k == 0 {
+            return Err(Error::InvalidSpec(format!("k {} is too small", k)));
+        }
 
         // Check that the graph is big enough to accommodate k connections
         // at least in the center
-        debug_assert!(
-            self.size() - 2 * k > 0,
-            "the graph of size {} is too small for a k {}",
-            self.size(),
-            k
-        );
+        if self.size() <= 2 * k {
+            return Err(Error::InvalidSpec(format!(
+                "the graph of size {} is too small for a k {}",
+                self.size(),
+                k
+            )));
+        }
 
         for node in 0..self.size() {
             let smallest_parent = max(node as isize - k as isize, 0) as usize;
-            self.parents.push((smallest_parent..node).collect());
+            self.parents
+                .push((smallest_parent..node).map(I::from_node).collect());
         }
+        Ok(())
     }
 
-    // children_project returns the children edges denoted by this graph
-    // instead of using the parent relationship.
+    // children returns the children edges denoted by this graph instead of
+    // using the parent relationship, computing them (in parallel) the first time
+    // it's called.
     // If j = array[i][u] (for any u), then there is an edge (i -> j) in the graph.
     // Useful for the greedy attacks for example.
-    pub fn children_project(&mut self) -> &Vec<Vec<usize>> {
-        // compute only once
-        if self.children.len() == 0 {
-            let mut children = vec![vec![]; self.cap()];
-            for (node, parents) in self.parents.iter().enumerate() {
-                for &parent in parents.iter() {
-                    children[parent].push(node);
+    pub fn children(&self) -> &Vec<Vec<I>> {
+        self.children.get_or_init(|| {
+            // the out-degrees are counted in parallel, so each list is
+            // allocated once with its final size, then the lists are filled
+            // in parallel (locking the list of each parent) and sorted in
+            // parallel since the threads push the children in any order
+            let degrees: Vec<AtomicUsize> = (0..self.cap()).map(|_| AtomicUsize::new(0)).collect();
+            self.parents.par_iter().for_each(|parents| {
+                for parent in parents.iter() {
+                    degrees[parent.node()].fetch_add(1, Ordering::Relaxed);
                 }
-            }
-            self.children = children;
-        }
-        return &self.children;
-    }
-
-    pub fn children(&self) -> &Vec<Vec<usize>> {
-        if self.children.len() == 0 {
-            panic!("called children() without children_project() first");
-        }
-        return &self.children;
+            });
+            let children: Vec<Mutex<Vec<I>>> = degrees
+                .into_par_iter()
+                .map(|degree| Mutex::new(Vec::with_capacity(degree.into_inner())))
+                .collect();
+            self.parents
+                .par_iter()
+                .enumerate()
+                .for_each(|(node, parents)| {
+                    for parent in parents.iter() {
+                        children[parent.node()]
+                            .lock()
+                            .unwrap()
+                            .push(I::from_node(node));
+                    }
+                });
+            children
+                .into_par_iter()
+                .map(|children| {
+                    let mut children = children.into_inner().unwrap();
+                    children.sort_unstable();
+                    children
+                })
+                .collect()
+        })
     }
 
     /// Returns the number of edges
@@ -633,7 +1376,7 @@ impl Graph {
             .fold(0, |acc, parents| acc + parents.len())
     }
 
-    pub fn parents(&self) -> &Vec<Vec<Node>> {
+    pub fn parents(&self) -> &Vec<Vec<I>> {
         &self.parents
     }
     // FIXME: Remove this, at much return the parents of a single
@@ -641,9 +1384,10 @@ impl Graph {
 
     pub fn for_each_edge<F>(&self, mut func: F)
     where
-        F: FnMut(&Edge) -> (),
+        F: FnMut(&Edge<I>) -> (),
     {
         for (child, all_parents) in self.parents().iter().enumerate() {
+            let child = I::from_node(child);
             for &parent in all_parents.iter() {
                 func(&Edge::new(parent, child));
                 // FIXME: PERF: Maybe don't construct a new edge in every call.
@@ -667,26 +1411,26 @@ impl Graph {
         self.parents.len()
     }
 
-    pub fn stats(&self) -> String {
-        let mut parents = HashMap::new();
-        let mut children = HashMap::new();
-        self.for_each_edge(|edge| {
-            let count = parents.entry(edge.child).or_insert(0);
-            *count += 1;
-            let count = children.entry(edge.parent).or_insert(0);
-            *count += 1;
+    pub fn stats(&self) -> GraphStats {
+        let mut in_degree = vec![];
+        let mut out_degree = vec![0; self.size()];
+        for parents in self.parents.iter() {
+            histogram_add(&mut in_degree, parents.len());
+            parents.iter().for_each(|p| out_degree[p.node()] += 1);
+        }
+        let out_degree = out_degree.into_iter().fold(vec![], |mut acc, d| {
+            histogram_add(&mut acc, d);
+            acc
         });
-        for i in 0..=self.degree() {
-            parents.remove(&i);
+        GraphStats {
+            size: self.size(),
+            edges: self.count_edges(),
+            depth: self.depth(),
+            in_degree,
+            out_degree,
+            edge_lengths: self.buckets(),
+            collapsed: self.collapsed.clone(),
         }
-        let min_parent = parents.values().min().unwrap();
-        let max_children = children.values().max().unwrap();
-        format!(
-            "graph stats: size={}, min parents={}, max children={}",
-            self.size(),
-            min_parent,
-            max_children
-        )
     }
 
     pub fn degree(&self) -> usize {
@@ -695,6 +1439,9 @@ impl Graph {
             DRGAlgo::MetaBucket(deg) => deg,
             DRGAlgo::KConnector(d) => d,
             DRGAlgo::Ren21(d) => d,
+            DRGAlgo::PowerLaw(d, _) => d,
+            DRGAlgo::Imported => self.parents.iter().map(|p| p.len()).max().unwrap_or(0),
+            DRGAlgo::Custom(d) => d,
         }
     }
 
@@ -703,18 +1450,18 @@ impl Graph {
     /// set attacks are using the property that different buckets have different
     /// highly variable sizes, DRSample offers a core protection against these
     /// attacks by forcing the buckets to have ~ equal sizes.
-    #[cfg(test)]
-    fn buckets(&self) -> Vec<usize> {
+    /// The bucket `i` counts the edges `(u,v)` with `2^(i-1) < v - u <= 2^i`.
+    pub fn buckets(&self) -> Vec<usize> {
         let log = (self.cap() as f32).log2().ceil() as usize;
         let mut ret = vec![0; log + 1];
         self.for_each_edge(|edge| {
-            // dist = | u - v |
-            let dist = (edge.child as i64 - edge.parent as i64).abs() as usize;
+            // dist = | u - v |, the parent is always smaller
+            let dist = edge.child.node() - edge.parent.node();
             // dist <= 2^î
             let i = (dist.next_power_of_two() as f32).log2().floor() as usize;
             ret[i] += 1;
         });
-        return ret;
+        ret
     }
 
     /// Convert the graph to a matrix where an `X` signals an edge
@@ -734,7 +1481,7 @@ impl Graph {
             for col in 0..self.size() {
                 matrix += format!(
                     "{: >3}",
-                    if self.parents[col].contains(&row) {
+                    if self.parents[col].contains(&I::from_node(row)) {
                         "X"
                     } else {
                         ""
@@ -748,7 +1495,7 @@ impl Graph {
     }
 }
 
-impl fmt::Display for Graph {
+impl<I: NodeIndex> fmt::Display for Graph<I> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "G(cap:{}, drg: ", self.parents.len())?;
         match self.algo() {
@@ -756,20 +1503,51 @@ impl fmt::Display for Graph {
             DRGAlgo::MetaBucket(d) => write!(f, "meta-bucket (degree {}), ", d)?,
             DRGAlgo::KConnector(k) => write!(f, "{}-connect, ", k)?,
             DRGAlgo::Ren21(d) => write!(f, "ren21(degree {})", d)?,
+            DRGAlgo::PowerLaw(d, e) => write!(f, "power-law(degree {}, exponent {})", d, e)?,
+            DRGAlgo::Imported => write!(f, "imported, ")?,
+            DRGAlgo::Custom(d) => write!(f, "custom(degree {}), ", d)?,
         }
         write!(f, "parents: {:?}", self.parents)
     }
 }
 
-fn remove_duplicate<T: Hash + Eq>(elements: &mut Vec<T>) {
-    let set: HashSet<_> = elements.drain(..).collect();
-    elements.extend(set.into_iter());
+/// Bucket index bound `ceil(log2(meta_idx))` of DRSample (see
+/// `Graph::sample_parent_node`). It's computed in `f32` as the graphs have
+/// always been generated, which gives one less for the indexes just above
+/// the powers of two from 2^21 (a bias the exact `conformance::drsample_pmf`
+/// detects).
+pub(crate) fn max_bucket(meta_idx: usize) -> usize {
+    (meta_idx as f32).log2().ceil() as usize
+}
+
+/// Remove duplicate elements returning how many were removed. The first
+/// occurrence of each element is kept in place, so the order (which the
+/// attacks break ties and sample parents with) only depends on the input.
+pub(crate) fn remove_duplicate<T: Hash + Eq + Copy>(elements: &mut Vec<T>) -> usize {
+    let before = elements.len();
+    let mut seen = HashSet::with_capacity(before);
+    elements.retain(|&element| seen.insert(element));
+    before - elements.len()
+}
+
+// convert the parents of a node to the index type of the graph
+fn to_index<I: NodeIndex>(nodes: Vec<Node>) -> Vec<I> {
+    nodes.into_iter().map(I::from_node).collect()
+}
+
+// increment the count of `value` in the histogram
+fn histogram_add(histogram: &mut Vec<usize>, value: usize) {
+    if histogram.len() <= value {
+        histogram.resize(value + 1, 0);
+    }
+    histogram[value] += 1;
 }
 
 #[cfg(test)]
 pub mod tests {
 
     use super::*;
+    use crate::import::ImportError;
     use std::iter::FromIterator;
 
     pub static TEST_SEED: [u8; 32] = [1; 32];
@@ -777,14 +1555,14 @@ pub mod tests {
     #[test]
     fn graph_new() {
         let size = 100;
-        let g1 = Graph::new(size, TEST_SEED, DRGAlgo::BucketSample);
+        let g1 = Graph::new(size, TEST_SEED, DRGAlgo::BucketSample).unwrap();
         assert_eq!(g1.parents.len(), 100); // no nodes generated yet
         assert_eq!(g1.parents.capacity(), size);
     }
 
     #[test]
     fn graph_bucket_sample() {
-        let g1 = Graph::new(10, TEST_SEED, DRGAlgo::BucketSample);
+        let g1 = Graph::new(10, TEST_SEED, DRGAlgo::BucketSample).unwrap();
         g1.parents.iter().enumerate().for_each(|(i, parents)| {
             if i == 0 {
                 return;
@@ -804,7 +1582,7 @@ pub mod tests {
     #[test]
     fn graph_meta_sample() {
         let degree = 3;
-        let g1 = Graph::new(10, TEST_SEED, DRGAlgo::MetaBucket(3));
+        let g1 = Graph::new(10, TEST_SEED, DRGAlgo::MetaBucket(3)).unwrap();
         g1.parents.iter().enumerate().for_each(|(i, parents)| {
             if i == 0 {
                 return;
@@ -829,16 +1607,34 @@ pub mod tests {
     }
 
     #[test]
-    fn graph_children_project() {
+    fn graph_children() {
         // graph 1 - 5 nodes
         // 0 -> 1, 1 -> 2, 2 -> 3, 3 -> 4
         // 0 -> 2, 2 -> 4
         let p1 = vec![vec![], vec![0], vec![0, 1], vec![2], vec![2, 3]];
-        let mut g1 = graph_from(p1);
+        let g1 = graph_from(p1);
 
-        let children = g1.children_project();
+        let children = g1.children();
         let exp = vec![vec![1, 2], vec![2], vec![3, 4], vec![4], vec![]];
         assert_eq!(children, &exp);
+        // computed only once
+        assert!(std::ptr::eq(children, g1.children()));
+    }
+
+    #[test]
+    fn graph_new_errors() {
+        assert!(matches!(
+            Graph::new(10, TEST_SEED, DRGAlgo::Imported),
+            Err(Error::Unsupported(DRGAlgo::Imported))
+        ));
+        assert!(matches!(
+            Graph::new(10, TEST_SEED, DRGAlgo::KConnector(0)),
+            Err(Error::InvalidSpec(_))
+        ));
+        assert!(matches!(
+            Graph::new(10, TEST_SEED, DRGAlgo::KConnector(5)),
+            Err(Error::InvalidSpec(_))
+        ));
     }
 
     #[test]
@@ -867,10 +1663,36 @@ pub mod tests {
         assert_eq!(graph_from(p2).depth(), 3);
 
         let size = 2048;
-        let g3 = Graph::new(size, TEST_SEED, DRGAlgo::MetaBucket(3));
+        let g3 = Graph::new(size, TEST_SEED, DRGAlgo::MetaBucket(3)).unwrap();
         assert_eq!(g3.depth(), size - 1);
     }
 
+    #[test]
+    fn graph_stats() {
+        // 0 -> 1 -> 2 -> 3, 0 -> 2, 0 -> 3
+        let g1 = graph_from(vec![vec![], vec![0], vec![0, 1], vec![0, 2]]);
+        let stats = g1.stats();
+        assert_eq!(stats.edges, 5);
+        assert_eq!(stats.depth, 3);
+        assert_eq!(stats.in_degree, vec![1, 1, 2]);
+        assert_eq!(stats.out_degree, vec![1, 2, 0, 1]);
+        // distances 1, 2, 1, 3, 1
+        assert_eq!(stats.edge_lengths, vec![3, 1, 1]);
+        assert!(stats.collapsed.is_empty());
+        // no panic on empty graphs
+        assert_eq!(graph_from(vec![]).stats().depth, 0);
+
+        let size = 1 << 10;
+        let g2 = Graph::new(size, TEST_SEED, DRGAlgo::MetaBucket(6)).unwrap();
+        let stats = g2.stats();
+        assert_eq!(stats.collapsed.iter().sum::<usize>(), size);
+        // every sampled parent either survived or was collapsed
+        let collapsed: usize = stats.collapsed.iter().enumerate().map(|(i, c)| i * c).sum();
+        assert_eq!(stats.edges + collapsed, (size - 2) * 6 + 1);
+        assert_eq!(stats.in_degree.iter().sum::<usize>(), size);
+        assert_eq!(stats.edge_lengths.iter().sum::<usize>(), stats.edges);
+    }
+
     #[test]
     fn graph_count_edges() {
         let p1 = vec![vec![], vec![0], vec![1], vec![2], vec![3]];
@@ -886,7 +1708,7 @@ pub mod tests {
         let s = ExclusionSet::from_nodes(&g1, vec![2]);
         assert_eq!(g1.depth_exclude(&s), 1);
 
-        let g2 = Graph::new(17, TEST_SEED, DRGAlgo::MetaBucket(3));
+        let g2 = Graph::new(17, TEST_SEED, DRGAlgo::MetaBucket(3)).unwrap();
         let s = ExclusionSet::from_nodes(&g2, vec![2, 8, 15, 5, 10]);
         let depthex = g2.depth_exclude(&s);
         assert!(depthex < (g2.cap() - s.size()));
@@ -894,7 +1716,7 @@ pub mod tests {
         assert_eq!(g3.depth(), depthex);
 
         let size = (2 as usize).pow(10);
-        let g3 = Graph::new(size, TEST_SEED, DRGAlgo::MetaBucket(3));
+        let g3 = Graph::new(size, TEST_SEED, DRGAlgo::MetaBucket(3)).unwrap();
         assert!(g3.depth() < size);
         let ssize = 2 ^ 6;
         let mut rng = ChaChaRng::from_seed(TEST_SEED);
@@ -905,6 +1727,227 @@ pub mod tests {
         assert!(g3.depth_exclude(&sv) < size);
     }
 
+    #[test]
+    fn graph_longest_path_exclude() {
+        let p1 = vec![vec![], vec![0], vec![1], vec![2], vec![3], vec![0, 4]];
+        let g1 = graph_from(p1);
+        let s = ExclusionSet::new(&g1);
+        assert_eq!(g1.longest_path_exclude(&s), vec![0, 1, 2, 3, 4, 5]);
+        let s = ExclusionSet::from_nodes(&g1, vec![2]);
+        assert_eq!(g1.longest_path_exclude(&s), vec![3, 4, 5]);
+
+        let g2 = Graph::new(1 << 8, TEST_SEED, DRGAlgo::MetaBucket(3)).unwrap();
+        let s = ExclusionSet::from_nodes(&g2, (0..g2.size()).step_by(7).collect());
+        let path = g2.longest_path_exclude(&s);
+        assert_eq!(path.len(), g2.depth_exclude(&s) + 1);
+        assert!(path.iter().all(|&n| !s.contains(n)));
+        assert!(path.windows(2).all(|w| g2.parents()[w[1]].contains(&w[0])));
+    }
+
+    #[test]
+    fn stamped_node_set() {
+        let mut set = StampedNodeSet::new(10);
+        assert!(set.insert(3) && set.insert(7));
+        assert!(!set.insert(3));
+        assert!(set.contains(3) && !set.contains(4));
+        assert_eq!(set.iter().collect::<Vec<Node>>(), vec![3, 7]);
+        set.clear();
+        assert!(set.is_empty() && !set.contains(3));
+        assert!(set.insert(3));
+
+        // the stamps are reset when the epoch wraps around
+        set.epoch = u32::MAX;
+        set.insert(5);
+        set.clear();
+        assert_eq!(set.epoch, 1);
+        assert!((0..10).all(|node| !set.contains(node)));
+    }
+
+    #[test]
+    fn exclusion_set_bitset() {
+        let a = ExclusionSet::from_nodes(&graph_from(vec![vec![]; 130]), vec![0, 3, 64, 65, 129]);
+        let mut b = ExclusionSet::new_with_size(130);
+        (60..70).for_each(|node| b.insert(node));
+        b.remove(61);
+        assert_eq!(a.size(), 5);
+        assert_eq!(b.size(), 9);
+        assert_eq!(a.iter().collect::<Vec<Node>>(), vec![0, 3, 64, 65, 129]);
+
+        let union = a.union(&b);
+        assert_eq!(union.size(), 5 + 9 - 2);
+        assert!(union.contains(3) && union.contains(62) && !union.contains(61));
+        let intersection = a.intersection(&b);
+        assert_eq!(intersection.iter().collect::<Vec<Node>>(), vec![64, 65]);
+        let difference = a.difference(&b);
+        assert_eq!(difference.iter().collect::<Vec<Node>>(), vec![0, 3, 129]);
+        let mut c = a.clone();
+        c.subtract(&a);
+        assert!(c.is_empty());
+        assert_eq!(c, ExclusionSet::new_with_size(130));
+    }
+
+    #[test]
+    fn graph_load_checks_parents() {
+        let path = std::env::temp_dir().join(format!("drg-load-{}.json", std::process::id()));
+        let fname = path.to_str().unwrap();
+        let g = graph_from(vec![vec![], vec![0], vec![0, 1]]);
+        g.save(fname).unwrap();
+        assert_eq!(Graph::load(fname).unwrap().parents(), g.parents());
+
+        for (parents, expected) in [
+            (vec![vec![], vec![2], vec![0]], ImportError::Unordered(2, 1)),
+            (vec![vec![], vec![0, 5]], ImportError::OutOfRange(5)),
+            (vec![vec![], vec![1]], ImportError::SelfLoop(1)),
+        ] {
+            graph_from(parents).save(fname).unwrap();
+            match Graph::load(fname) {
+                Err(Error::Import(e)) => assert_eq!(e.to_string(), expected.to_string()),
+                other => panic!("unexpected result {:?}", other),
+            }
+        }
+
+        let mut g = graph_from(vec![vec![], vec![0]]);
+        g.spec.size = 3;
+        g.save(fname).unwrap();
+        assert!(matches!(Graph::load(fname), Err(Error::InvalidSpec(_))));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "out of the set range")]
+    fn exclusion_set_out_of_range() {
+        // node in the padding of the last word
+        let mut s = ExclusionSet::new_with_size(130);
+        s.insert(130);
+    }
+
+    #[test]
+    fn exclusion_set_serialization() {
+        let mut s = ExclusionSet::new_with_size(1000);
+        (0..1000).step_by(100).for_each(|node| s.insert(node));
+        (500..700).for_each(|node| s.insert(node));
+        let runs = s.runs();
+        assert_eq!(runs[..4], [0, 1, 99, 1]);
+        assert_eq!(runs.iter().sum::<usize>(), 1000);
+        assert_eq!(ExclusionSet::from_runs(1000, &runs), Ok(s.clone()));
+        assert_eq!(
+            ExclusionSet::from_runs(999, &runs),
+            Err(ExclusionSetError::RunsLength {
+                len: 999,
+                covered: 1000
+            })
+        );
+
+        let bytes = s.to_bytes();
+        assert!(bytes.len() < 32);
+        assert_eq!(ExclusionSet::from_bytes(&bytes, 1000), Ok(s.clone()));
+        assert_eq!(
+            ExclusionSet::from_bytes(&bytes[..bytes.len() - 1], 1000),
+            Err(ExclusionSetError::Truncated)
+        );
+        assert_eq!(
+            ExclusionSet::from_bytes(&bytes, 999),
+            Err(ExclusionSetError::Len {
+                expected: 999,
+                len: 1000
+            })
+        );
+
+        // crafted huge sets are rejected before allocating them
+        let mut huge = Vec::new();
+        write_varint(&mut huge, 1 << 60);
+        write_varint(&mut huge, 2);
+        write_varint(&mut huge, 0);
+        write_varint(&mut huge, 1 << 60);
+        assert_eq!(
+            ExclusionSet::from_bytes(&huge, 1 << 60),
+            Err(ExclusionSetError::TooLarge { len: 1 << 60 })
+        );
+        assert!(ExclusionSet::from_bytes(&huge, 1000).is_err());
+        let json = r#"{"len":1152921504606846976,"runs":[0,1152921504606846976]}"#;
+        assert!(serde_json::from_str::<ExclusionSet>(json).is_err());
+
+        // varints with bits beyond a usize
+        let mut max = Vec::new();
+        write_varint(&mut max, usize::MAX);
+        assert_eq!(read_varint(&mut &max[..]).unwrap(), usize::MAX);
+        let last = max.len() - 1;
+        for overflow in [max[last] | 0x02, max[last] | 0x80] {
+            let mut bytes = max.clone();
+            bytes[last] = overflow;
+            bytes.push(0);
+            let err = read_varint(&mut &bytes[..]).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+
+        let json = serde_json::to_string(&s).unwrap();
+        assert_eq!(serde_json::from_str::<ExclusionSet>(&json).unwrap(), s);
+        assert!(serde_json::from_str::<ExclusionSet>(r#"{"len":3,"runs":[1,1]}"#).is_err());
+    }
+
+    #[test]
+    fn graph_depths_exclude() {
+        // 0->1->2->3->4->5, 0->5 and 2 excluded
+        let g1 = graph_from(vec![vec![], vec![0], vec![1], vec![2], vec![3], vec![0, 4]]);
+        let s = ExclusionSet::from_nodes(&g1, vec![2]);
+        let (forward, backward) = g1.depths_exclude(&s);
+        assert_eq!(forward, vec![0, 1, 0, 0, 1, 2]);
+        assert_eq!(backward, vec![1, 0, 0, 2, 1, 0]);
+
+        let g2 = Graph::new(1 << 8, TEST_SEED, DRGAlgo::MetaBucket(3)).unwrap();
+        let s = ExclusionSet::from_nodes(&g2, (0..g2.size()).step_by(7).collect());
+        let (forward, backward) = g2.depths_exclude(&s);
+        let depth = g2.depth_exclude(&s);
+        assert_eq!(forward.iter().max(), Some(&depth));
+        assert_eq!(backward.iter().max(), Some(&depth));
+        // the nodes of a longest path are exactly at the maximum total depth
+        for node in g2.longest_path_exclude(&s) {
+            assert_eq!(forward[node] + backward[node], depth);
+        }
+    }
+
+    #[test]
+    fn graph_node_index() {
+        assert!(u32::fits(1 << 32));
+        assert!(!u32::fits((1 << 32) + 1));
+        assert!(u64::fits(usize::MAX));
+        assert_eq!(u32::try_from_node(1 << 32), None);
+        assert_eq!(u64::from_node(usize::MAX).node(), usize::MAX);
+        assert!(IndexWidth::Auto.is_u32(1 << 32));
+        assert!(!IndexWidth::Auto.is_u32((1 << 32) + 1));
+        assert!(!IndexWidth::Usize.is_u32(1 << 8));
+
+        // the same graph whatever the index type
+        let spec = GraphSpec {
+            size: 1 << 8,
+            seed: TEST_SEED,
+            algo: DRGAlgo::MetaBucket(3),
+        };
+        let g = Graph::new_from_rng(spec, &mut ChaChaRng::from_seed(TEST_SEED)).unwrap();
+        let g32 = Graph::<u32>::generate(spec, &mut ChaChaRng::from_seed(TEST_SEED)).unwrap();
+        assert_eq!(g32.size(), g.size());
+        for (parents, parents32) in g.parents().iter().zip(g32.parents().iter()) {
+            let parents32: HashSet<Node> = parents32.iter().map(|p| p.node()).collect();
+            assert_eq!(parents32, parents.iter().cloned().collect());
+        }
+        let s = ExclusionSet::from_nodes(&g, (0..g.size()).step_by(5).collect());
+        assert_eq!(g32.depth_exclude(&s), g.depth_exclude(&s));
+        assert_eq!(g32.depths_exclude(&s), g.depths_exclude(&s));
+    }
+
+    #[test]
+    fn graph_node_index_overflow() {
+        let spec = GraphSpec {
+            size: (1 << 32) + 1,
+            seed: TEST_SEED,
+            algo: DRGAlgo::KConnector(1),
+        };
+        assert!(matches!(
+            Graph::<u32>::generate(spec, &mut ChaChaRng::from_seed(TEST_SEED)),
+            Err(Error::IndexOverflow { bits: 32, .. })
+        ));
+    }
+
     #[test]
     fn graph_depth_exclude_edges() {
         // 0->1->-2->3->4->5
@@ -921,7 +1964,7 @@ pub mod tests {
     fn graph_ren21() {
         let degree = 5;
         let size = 1 << 13;
-        let ren21 = Graph::new(size, TEST_SEED, DRGAlgo::Ren21(degree));
+        let ren21 = Graph::new(size, TEST_SEED, DRGAlgo::Ren21(degree)).unwrap();
         let nb_parents = ren21
             .parents()
             .iter()
@@ -938,6 +1981,77 @@ pub mod tests {
         assert!(degree as f32 + 0.5 > mean_parents);
     }
 
+    #[test]
+    fn graph_power_law() {
+        let size = 1 << 13;
+        let g = Graph::new(size, TEST_SEED, DRGAlgo::PowerLaw(4, 2.0)).unwrap();
+        let (mut short, mut long) = (0, 0);
+        g.for_each_edge(|edge| {
+            match edge.child - edge.parent {
+                1 => {}
+                2..=8 => short += 1,
+                _ => long += 1,
+            };
+        });
+        // with an exponent of 2 most of the mass is in the shortest distances
+        assert!(short > long);
+        assert!(g.parents().iter().skip(1).all(|p| p.len() <= 4));
+    }
+
+    #[test]
+    fn graph_custom_sampler() {
+        struct FixedDistance;
+        impl ParentSampler for FixedDistance {
+            fn degree(&self) -> usize {
+                2
+            }
+            fn back_distance(&self, _: Node, _: &mut ChaChaRng) -> usize {
+                2
+            }
+        }
+        let spec = GraphSpec {
+            size: 6,
+            seed: TEST_SEED,
+            algo: DRGAlgo::Custom(2),
+        };
+        let mut g = Graph::new_with_sampler(spec, &FixedDistance).unwrap();
+        g.parents.iter_mut().for_each(|p| p.sort());
+        let exp = vec![
+            vec![],
+            vec![0],
+            vec![0, 1],
+            vec![1, 2],
+            vec![2, 3],
+            vec![3, 4],
+        ];
+        assert_eq!(g.parents, exp);
+        assert_eq!(g.algo(), DRGAlgo::Custom(2));
+        // the spec can't claim another construction
+        let spec = GraphSpec {
+            algo: DRGAlgo::KConnector(2),
+            ..spec
+        };
+        assert!(matches!(
+            Graph::new_with_sampler(spec, &FixedDistance),
+            Err(Error::InvalidSpec(_))
+        ));
+
+        // the built-in constructions are samplers as well
+        let spec = GraphSpec {
+            size: 1 << 10,
+            seed: TEST_SEED,
+            algo: DRGAlgo::MetaBucket(3),
+        };
+        let g1 = Graph::new_with_sampler(spec, &MetaBucketSampler { degree: 3 }).unwrap();
+        let g2 = Graph::new(spec.size, spec.seed, spec.algo).unwrap();
+        let sorted = |g: &Graph| {
+            let mut parents = g.parents().clone();
+            parents.iter_mut().for_each(|p| p.sort());
+            parents
+        };
+        assert_eq!(sorted(&g1), sorted(&g2));
+    }
+
     #[test]
     /// This test is testing the distribution of the edges of both bucket sample and
     /// drsample. As indicated in Alwen et al., when separating the edges into different
@@ -987,9 +2101,9 @@ pub mod tests {
                     .count()
             );
         };
-        let drsample = Graph::new(size, TEST_SEED, DRGAlgo::BucketSample);
+        let drsample = Graph::new(size, TEST_SEED, DRGAlgo::BucketSample).unwrap();
         test_dist(&drsample);
-        let bucket = Graph::new(size, TEST_SEED, DRGAlgo::MetaBucket(3));
+        let bucket = Graph::new(size, TEST_SEED, DRGAlgo::MetaBucket(3)).unwrap();
         test_dist(&bucket);
     }
     fn mean(data: &[usize]) -> Option<f32> {
@@ -1031,7 +2145,8 @@ pub mod tests {
             //  parents (copying parents is almost like copying the entire
             //  graph for that matter).
             parents: parents,
-            children: vec![],
+            children: OnceLock::new(),
+            collapsed: vec![],
         }
     }
 
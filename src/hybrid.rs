@@ -0,0 +1,272 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::attacks::{greedy_reduce_main, valiant_partitions, AttackAlgo, GreedyParams};
+use crate::error::{Error, Result};
+use crate::graph::{ExclusionSet, Graph, Node, NodeIndex};
+
+/// Stage of a hybrid attack, all of them working on the same exclusion set
+/// and stopping as soon as the target depth is reached (except pruning).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Stage {
+    /// Add the origin nodes of (at most) this number of the smallest msbd
+    /// partitions of the edges left in G-S, like Valiant's attack.
+    Valiant { partitions: usize },
+    /// Greedy attack ranking the nodes by incident paths, adding at most
+    /// `max_added` nodes (rounded up to the `k` nodes of its last iteration).
+    GreedyPaths {
+        params: GreedyParams,
+        max_added: usize,
+    },
+    /// Greedy attack ranking the nodes by degree in G-S (ignores
+    /// `params.use_degree`), adding at most `max_added` nodes.
+    GreedyDegree {
+        params: GreedyParams,
+        max_added: usize,
+    },
+    /// Remove from S the nodes that aren't needed to keep the target depth,
+    /// trying the ones with the lowest degree first.
+    Prune,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Stage::Valiant { partitions } => write!(f, "valiant({} partitions)", partitions),
+            Stage::GreedyPaths { max_added, .. } => write!(f, "greedy-paths(max {})", max_added),
+            Stage::GreedyDegree { max_added, .. } => {
+                write!(f, "greedy-degree(max {})", max_added)
+            }
+            Stage::Prune => write!(f, "prune"),
+        }
+    }
+}
+
+/// Contribution of a stage to the attack.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageReport {
+    pub stage: String,
+    pub size_before: usize,
+    pub size_after: usize,
+    pub depth_before: usize,
+    pub depth_after: usize,
+    pub elapsed: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct HybridReport {
+    pub set: ExclusionSet,
+    pub stages: Vec<StageReport>,
+}
+
+impl fmt::Display for HybridReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<24} {:>10} {:>10} {:>10} {:>10}",
+            "stage", "|S|", "delta |S|", "depth", "delta d"
+        )?;
+        for r in self.stages.iter() {
+            writeln!(
+                f,
+                "{:<24} {:>10} {:>+10} {:>10} {:>+10}",
+                r.stage,
+                r.size_after,
+                r.size_after as isize - r.size_before as isize,
+                r.depth_after,
+                r.depth_after as isize - r.depth_before as isize,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Run the `stages` in order on a shared exclusion set to reduce the depth
+/// of G-S to `depth`, recording the size and depth after each of them.
+pub fn hybrid_reduce_with_report<I: NodeIndex>(
+    g: &Graph<I>,
+    depth: usize,
+    stages: &[Stage],
+) -> Result<HybridReport> {
+    let mut s = ExclusionSet::new(g);
+    let mut reports = Vec::with_capacity(stages.len());
+    for stage in stages.iter() {
+        let start = Instant::now();
+        let (size_before, depth_before) = (s.size(), g.depth_exclude(&s));
+        s = match stage {
+            Stage::Valiant { partitions } => valiant_stage(g, s, depth, *partitions),
+            Stage::GreedyPaths { params, max_added } => {
+                greedy_stage(g, s, depth, params.clone(), *max_added)?
+            }
+            Stage::GreedyDegree { params, max_added } => {
+                let params = GreedyParams {
+                    use_degree: true,
+                    ..params.clone()
+                };
+                greedy_stage(g, s, depth, params, *max_added)?
+            }
+            Stage::Prune => prune_stage(g, s, depth),
+        };
+        let report = StageReport {
+            stage: stage.to_string(),
+            size_before,
+            size_after: s.size(),
+            depth_before,
+            depth_after: g.depth_exclude(&s),
+            elapsed: start.elapsed(),
+        };
+        debug!(
+            "\t-> stage {}: |S| {} -> {}, depth(G-S) {} -> {}",
+            report.stage,
+            report.size_before,
+            report.size_after,
+            report.depth_before,
+            report.depth_after
+        );
+        reports.push(report);
+    }
+    Ok(HybridReport {
+        set: s,
+        stages: reports,
+    })
+}
+
+fn valiant_stage<I: NodeIndex>(
+    g: &Graph<I>,
+    mut s: ExclusionSet,
+    depth: usize,
+    partitions: usize,
+) -> ExclusionSet {
+    // partitions of the edges of G-S, smallest first
+    let mut remaining: Vec<Vec<Node>> = valiant_partitions(g)
+        .iter()
+        .map(|partition| {
+            partition
+                .iter()
+                .filter(|edge| !s.contains(edge.parent.node()) && !s.contains(edge.child.node()))
+                .map(|edge| edge.parent.node())
+                .collect::<Vec<Node>>()
+        })
+        .filter(|origins| !origins.is_empty())
+        .collect();
+    remaining.sort_by_key(|origins| origins.len());
+    for origins in remaining.iter().take(partitions) {
+        if g.depth_exclude(&s) <= depth {
+            break;
+        }
+        origins.iter().for_each(|&node| s.insert(node));
+    }
+    s
+}
+
+fn greedy_stage<I: NodeIndex>(
+    g: &Graph<I>,
+    s: ExclusionSet,
+    depth: usize,
+    params: GreedyParams,
+    max_added: usize,
+) -> Result<ExclusionSet> {
+    let max_size = s.size().saturating_add(max_added);
+    let scorer = params.scoring.scorer();
+    greedy_reduce_main(
+        g,
+        s,
+        params,
+        scorer.as_ref(),
+        &|set: &ExclusionSet, g: &Graph<I>| set.size() < max_size && g.depth_exclude(set) > depth,
+        None,
+    )
+}
+
+fn prune_stage<I: NodeIndex>(g: &Graph<I>, mut s: ExclusionSet, depth: usize) -> ExclusionSet {
+    if g.depth_exclude(&s) > depth {
+        // nothing to keep
+        return s;
+    }
+    let mut members: Vec<Node> = s.iter().collect();
+    let mut degree = vec![0; g.size()];
+    g.for_each_edge(|edge| {
+        degree[edge.parent.node()] += 1;
+        degree[edge.child.node()] += 1;
+    });
+    members.sort_by_key(|&node| degree[node]);
+    for node in members.into_iter() {
+        s.remove(node);
+        if g.depth_exclude(&s) > depth {
+            s.insert(node);
+        }
+    }
+    s
+}
+
+pub(crate) fn hybrid_reduce<I: NodeIndex>(g: &Graph<I>, d: AttackAlgo) -> Result<ExclusionSet> {
+    match d {
+        AttackAlgo::HybridDepth(depth, stages) => {
+            Ok(hybrid_reduce_with_report(g, depth, &stages)?.set)
+        }
+        _ => Err(Error::InvalidAttack(Box::new(d))),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::attacks::depth_reduce;
+    use crate::graph::{self, DRGAlgo};
+
+    #[test]
+    fn hybrid_stages() {
+        let size = 512;
+        let g = Graph::new(size, graph::tests::TEST_SEED, DRGAlgo::MetaBucket(3)).unwrap();
+        let depth = size / 8;
+        let params = GreedyParams {
+            k: 4,
+            radius: 1,
+            length: 8,
+            reset: true,
+            iter_topk: true,
+            ..GreedyParams::default()
+        };
+        let stages = vec![
+            Stage::Valiant { partitions: 1 },
+            Stage::GreedyDegree {
+                params: params.clone(),
+                max_added: 8,
+            },
+            Stage::GreedyPaths {
+                params,
+                max_added: usize::MAX,
+            },
+            Stage::Prune,
+        ];
+        let report = hybrid_reduce_with_report(&g, depth, &stages).unwrap();
+        assert_eq!(report.stages.len(), 4);
+        assert!(g.depth_exclude(&report.set) <= depth);
+        // each stage starts where the previous one ended
+        for w in report.stages.windows(2) {
+            assert_eq!(w[0].size_after, w[1].size_before);
+            assert_eq!(w[0].depth_after, w[1].depth_before);
+        }
+        // the last greedy iteration can overshoot by `k - 1` nodes
+        assert!(report.stages[1].size_after - report.stages[1].size_before < 8 + 4);
+        // pruning never grows the set or breaks the target
+        assert!(report.stages[3].size_after <= report.stages[3].size_before);
+        assert_eq!(report.stages[3].size_after, report.set.size());
+
+        let s = depth_reduce(&g, AttackAlgo::HybridDepth(depth, stages)).unwrap();
+        assert_eq!(s, report.set);
+    }
+
+    #[test]
+    fn hybrid_prune() {
+        // 0 -> 1 -> 2 -> 3 with S = {1, 2}: only one of them is needed
+        let g = graph::tests::graph_from(vec![vec![], vec![0], vec![1], vec![2]]);
+        let s = ExclusionSet::from_nodes(&g, vec![1, 2]);
+        let pruned = prune_stage(&g, s, 1);
+        assert_eq!(pruned.size(), 1);
+        assert!(g.depth_exclude(&pruned) <= 1);
+    }
+}
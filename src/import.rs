@@ -0,0 +1,415 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::graph::{DRGAlgo, Graph, GraphSpec, Node};
+
+/// Largest number of nodes of an imported graph (the parents alone take
+/// ~100 GiB), so a huge node id in the input can't request arbitrary
+/// allocations.
+pub const MAX_SIZE: usize = 1 << 32;
+
+/// Supported formats to import DAGs produced outside of this crate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportFormat {
+    /// One `parent child` edge per line (separated by spaces, tabs or commas),
+    /// with 0-based node ids. Lines starting with `#` or `%` are comments.
+    EdgeList,
+    /// DIMACS-style description: an optional `p <problem> <nodes> <edges>`
+    /// header followed by `a <parent> <child>` (or `e`) lines with 1-based
+    /// node ids (up to `<nodes>` if there's a header). Lines starting with
+    /// `c` are comments.
+    Dimacs,
+    /// JSON object with a `parents` array where the `i`-th entry holds the
+    /// parents of node `i` (the same layout of our own `Graph` dumps).
+    JsonParents,
+}
+
+impl ImportFormat {
+    /// Guess the format from the extension of the file (`.json`, `.dimacs`,
+    /// `.gr` or `.col`), defaulting to an edge list otherwise.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => ImportFormat::JsonParents,
+            Some("dimacs") | Some("gr") | Some("col") => ImportFormat::Dimacs,
+            _ => ImportFormat::EdgeList,
+        }
+    }
+}
+
+/// What to do with graphs that don't follow the *proper* labelling that
+/// `Graph` assumes (for each edge `(i,j)`, `i < j`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Labelling {
+    /// Reject the graph.
+    Strict,
+    /// Relabel the nodes in a topological order (keeping the original order
+    /// whenever possible).
+    Topological,
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    /// Malformed line (1-based line number and content).
+    Parse(usize, String),
+    /// Edge that doesn't follow the proper labelling with `Labelling::Strict`.
+    Unordered(Node, Node),
+    SelfLoop(Node),
+    /// Node id bigger than the declared size of the graph.
+    OutOfRange(Node),
+    /// Number of nodes of a graph bigger than `MAX_SIZE`.
+    TooLarge(usize),
+    Cycle,
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImportError::Io(e) => write!(f, "io error: {}", e),
+            ImportError::Json(e) => write!(f, "json error: {}", e),
+            ImportError::Parse(line, content) => {
+                write!(f, "can't parse line {}: {:?}", line, content)
+            }
+            ImportError::Unordered(parent, child) => write!(
+                f,
+                "the parent {} is not smaller than child {} (try relabelling)",
+                parent, child
+            ),
+            ImportError::SelfLoop(node) => write!(f, "self loop in node {}", node),
+            ImportError::OutOfRange(node) => write!(f, "node {} is out of range", node),
+            ImportError::TooLarge(size) => write!(
+                f,
+                "graph of {} nodes is bigger than the maximum of {}",
+                size, MAX_SIZE
+            ),
+            ImportError::Cycle => write!(f, "the graph is not acyclic"),
+        }
+    }
+}
+
+impl error::Error for ImportError {}
+
+impl From<io::Error> for ImportError {
+    fn from(e: io::Error) -> Self {
+        ImportError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ImportError {
+    fn from(e: serde_json::Error) -> Self {
+        ImportError::Json(e)
+    }
+}
+
+/// Imported graph along with the original label of each of its nodes (the
+/// identity unless it was topologically relabelled), to translate exclusion
+/// sets back to the source of the graph.
+#[derive(Debug)]
+pub struct ImportedGraph {
+    pub graph: Graph,
+    pub labels: Vec<Node>,
+}
+
+pub fn import_file(
+    path: &Path,
+    format: ImportFormat,
+    labelling: Labelling,
+) -> Result<ImportedGraph, ImportError> {
+    let reader = BufReader::new(File::open(path)?);
+    match format {
+        ImportFormat::EdgeList => from_edge_list(reader, labelling),
+        ImportFormat::Dimacs => from_dimacs(reader, labelling),
+        ImportFormat::JsonParents => from_json_parents(reader, labelling),
+    }
+}
+
+pub fn from_edge_list<R: BufRead>(
+    r: R,
+    labelling: Labelling,
+) -> Result<ImportedGraph, ImportError> {
+    let mut edges = Vec::new();
+    for (i, line) in r.lines().enumerate() {
+        let line = line?;
+        let content = line.trim();
+        if content.is_empty() || content.starts_with('#') || content.starts_with('%') {
+            continue;
+        }
+        let fields: Vec<&str> = content
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|f| !f.is_empty())
+            .collect();
+        match parse_nodes(&fields) {
+            Some((parent, child)) => edges.push((parent, child)),
+            None => return Err(ImportError::Parse(i + 1, line)),
+        }
+    }
+    let size = node_count(&edges)?;
+    build(size, edges, labelling)
+}
+
+pub fn from_dimacs<R: BufRead>(r: R, labelling: Labelling) -> Result<ImportedGraph, ImportError> {
+    let mut size = None;
+    let mut edges = Vec::new();
+    for (i, line) in r.lines().enumerate() {
+        let line = line?;
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let parsed = match fields.first() {
+            None | Some(&"c") => continue,
+            Some(&"p") if fields.len() >= 3 => fields[2].parse().ok().map(|n| size = Some(n)),
+            Some(&"a") | Some(&"e") => match parse_nodes(&fields[1..]) {
+                // 1-based ids
+                Some((parent, child)) if parent > 0 && child > 0 => {
+                    edges.push((parent - 1, child - 1));
+                    Some(())
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+        if parsed.is_none() {
+            return Err(ImportError::Parse(i + 1, line));
+        }
+    }
+    // the edges beyond the declared size are rejected when building the graph
+    let size = match size {
+        Some(size) => size,
+        None => node_count(&edges)?,
+    };
+    build(size, edges, labelling)
+}
+
+pub fn from_json_parents<R: Read>(
+    r: R,
+    labelling: Labelling,
+) -> Result<ImportedGraph, ImportError> {
+    #[derive(Deserialize)]
+    struct Parents {
+        parents: Vec<Vec<Node>>,
+    }
+    let parents: Parents = serde_json::from_reader(r)?;
+    let size = parents.parents.len();
+    let edges = parents
+        .parents
+        .into_iter()
+        .enumerate()
+        .flat_map(|(child, parents)| parents.into_iter().map(move |parent| (parent, child)))
+        .collect();
+    build(size, edges, labelling)
+}
+
+// number of nodes of the graph spanned by the `edges`
+fn node_count(edges: &[(Node, Node)]) -> Result<usize, ImportError> {
+    match edges.iter().map(|&(p, c)| std::cmp::max(p, c)).max() {
+        None => Ok(0),
+        // `usize::MAX + 1` nodes, reported saturated
+        Some(node) => node.checked_add(1).ok_or(ImportError::TooLarge(node)),
+    }
+}
+
+// parse the first two fields as a `(parent, child)` edge, ignoring the rest
+// (e.g., weights)
+fn parse_nodes(fields: &[&str]) -> Option<(Node, Node)> {
+    if fields.len() < 2 {
+        return None;
+    }
+    Some((fields[0].parse().ok()?, fields[1].parse().ok()?))
+}
+
+/// Check the edge `parent -> child` of a graph of `size` nodes, with
+/// `Labelling::Strict` the parent also has to be smaller than the child.
+pub(crate) fn check_edge(
+    parent: Node,
+    child: Node,
+    size: usize,
+    labelling: Labelling,
+) -> Result<(), ImportError> {
+    if parent == child {
+        return Err(ImportError::SelfLoop(parent));
+    }
+    if parent >= size || child >= size {
+        return Err(ImportError::OutOfRange(std::cmp::max(parent, child)));
+    }
+    if parent > child && labelling == Labelling::Strict {
+        return Err(ImportError::Unordered(parent, child));
+    }
+    Ok(())
+}
+
+fn build(
+    size: usize,
+    edges: Vec<(Node, Node)>,
+    labelling: Labelling,
+) -> Result<ImportedGraph, ImportError> {
+    if size > MAX_SIZE {
+        return Err(ImportError::TooLarge(size));
+    }
+    let mut parents = vec![vec![]; size];
+    for &(parent, child) in edges.iter() {
+        check_edge(parent, child, size, labelling)?;
+        parents[child].push(parent);
+    }
+    parents.iter_mut().for_each(|p| {
+        p.sort();
+        p.dedup();
+    });
+
+    let labels = if parents
+        .iter()
+        .enumerate()
+        .all(|(c, p)| p.iter().all(|&p| p < c))
+    {
+        (0..size).collect()
+    } else {
+        let order = topological_order(&parents).ok_or(ImportError::Cycle)?;
+        let mut new_label = vec![0; size];
+        for (label, &node) in order.iter().enumerate() {
+            new_label[node] = label;
+        }
+        let mut relabelled = vec![vec![]; size];
+        for (node, node_parents) in parents.iter().enumerate() {
+            relabelled[new_label[node]] = node_parents.iter().map(|&p| new_label[p]).collect();
+            relabelled[new_label[node]].sort();
+        }
+        parents = relabelled;
+        order
+    };
+
+    let spec = GraphSpec {
+        size,
+        seed: [0; 32],
+        algo: DRGAlgo::Imported,
+    };
+    Ok(ImportedGraph {
+        graph: Graph::from_parents(spec, parents),
+        labels,
+    })
+}
+
+// Kahn's algorithm always picking the smallest node available to stay as
+// close as possible to the original labelling, `None` if there's a cycle.
+fn topological_order(parents: &[Vec<Node>]) -> Option<Vec<Node>> {
+    let mut children = vec![vec![]; parents.len()];
+    for (node, node_parents) in parents.iter().enumerate() {
+        for &parent in node_parents.iter() {
+            children[parent].push(node);
+        }
+    }
+    let mut missing: Vec<usize> = parents.iter().map(|p| p.len()).collect();
+    let mut ready: BinaryHeap<Reverse<Node>> = missing
+        .iter()
+        .enumerate()
+        .filter(|&(_, &m)| m == 0)
+        .map(|(node, _)| Reverse(node))
+        .collect();
+    let mut order = Vec::with_capacity(parents.len());
+    while let Some(Reverse(node)) = ready.pop() {
+        order.push(node);
+        for &child in children[node].iter() {
+            missing[child] -= 1;
+            if missing[child] == 0 {
+                ready.push(Reverse(child));
+            }
+        }
+    }
+    if order.len() == parents.len() {
+        Some(order)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::tests::TEST_SEED;
+
+    #[test]
+    fn import_edge_list() {
+        let input = "# comment\n0 1\n1 2\n\n0,2\n2\t3 1.5\n";
+        let imported = from_edge_list(input.as_bytes(), Labelling::Strict).unwrap();
+        assert_eq!(
+            imported.graph.parents(),
+            &vec![vec![], vec![0], vec![0, 1], vec![2]]
+        );
+        assert_eq!(imported.labels, vec![0, 1, 2, 3]);
+        assert!(from_edge_list("0 1\nfoo\n".as_bytes(), Labelling::Strict).is_err());
+
+        // huge ids are rejected before allocating the graph
+        let huge = format!("0 1\n1 {}\n", usize::MAX);
+        match from_edge_list(huge.as_bytes(), Labelling::Strict) {
+            Err(ImportError::TooLarge(size)) => assert_eq!(size, usize::MAX),
+            other => panic!("unexpected import result {:?}", other),
+        }
+        let huge = format!("0 {}\n", MAX_SIZE);
+        match from_edge_list(huge.as_bytes(), Labelling::Strict) {
+            Err(ImportError::TooLarge(size)) => assert_eq!(size, MAX_SIZE + 1),
+            other => panic!("unexpected import result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn import_dimacs() {
+        let input = "c comment\np edge 5 3\na 1 2\na 2 3\ne 1 3\n";
+        let imported = from_dimacs(input.as_bytes(), Labelling::Strict).unwrap();
+        assert_eq!(
+            imported.graph.parents(),
+            &vec![vec![], vec![0], vec![0, 1], vec![], vec![]]
+        );
+
+        // without a header the size is the one spanned by the edges
+        let imported = from_dimacs("a 1 2\na 2 4\n".as_bytes(), Labelling::Strict).unwrap();
+        assert_eq!(imported.graph.size(), 4);
+
+        // edges beyond the declared number of nodes
+        let input = "p edge 3 2\na 1 2\na 2 4\n";
+        match from_dimacs(input.as_bytes(), Labelling::Strict) {
+            Err(ImportError::OutOfRange(3)) => {}
+            other => panic!("unexpected import result {:?}", other),
+        }
+        let input = format!("p edge {} 1\na 1 2\n", MAX_SIZE + 1);
+        match from_dimacs(input.as_bytes(), Labelling::Strict) {
+            Err(ImportError::TooLarge(size)) => assert_eq!(size, MAX_SIZE + 1),
+            other => panic!("unexpected import result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn import_json_parents() {
+        let g = Graph::new(64, TEST_SEED, DRGAlgo::MetaBucket(3)).unwrap();
+        let json = serde_json::to_string(&g).unwrap();
+        let imported = from_json_parents(json.as_bytes(), Labelling::Strict).unwrap();
+        assert_eq!(imported.graph.size(), g.size());
+        assert_eq!(imported.graph.depth(), g.depth());
+        assert_eq!(imported.graph.count_edges(), g.count_edges());
+    }
+
+    #[test]
+    fn import_relabel() {
+        // 3 -> 1 -> 0 -> 2
+        let input = "3 1\n1 0\n0 2\n";
+        match from_edge_list(input.as_bytes(), Labelling::Strict) {
+            Err(ImportError::Unordered(3, 1)) => {}
+            other => panic!("unexpected import result {:?}", other),
+        }
+        let imported = from_edge_list(input.as_bytes(), Labelling::Topological).unwrap();
+        assert_eq!(imported.labels, vec![3, 1, 0, 2]);
+        assert_eq!(
+            imported.graph.parents(),
+            &vec![vec![], vec![0], vec![1], vec![2]]
+        );
+
+        let cycle = "0 1\n1 2\n2 0\n";
+        match from_edge_list(cycle.as_bytes(), Labelling::Topological) {
+            Err(ImportError::Cycle) => {}
+            other => panic!("unexpected import result {:?}", other),
+        }
+    }
+}
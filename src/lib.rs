@@ -3,7 +3,21 @@
 extern crate lazy_static;
 extern crate rayon;
 
+pub mod annealing;
 pub mod attacks;
+pub mod conformance;
+pub mod error;
+pub mod evolution;
+pub mod exact;
+pub mod export;
 pub mod graph;
+pub mod hybrid;
+pub mod import;
+pub mod pebbling;
 pub mod results;
+pub mod scoring;
+pub mod stream;
+pub mod transform;
 pub mod utils;
+
+pub use error::{Error, Result};
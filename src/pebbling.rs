@@ -0,0 +1,474 @@
+use serde::{Deserialize, Serialize};
+use std::error;
+use std::fmt;
+
+use crate::graph::{ExclusionSet, Graph, Node, NodeIndex};
+
+/// Rules of the pebbling game: in the sequential one at most one pebble can
+/// be placed at each step, in the parallel one there's no limit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PebblingGame {
+    Sequential,
+    Parallel,
+}
+
+/// Costs of a (parallel) pebbling of a graph.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PebblingCost {
+    /// Number of pebbling steps.
+    pub time: usize,
+    /// Maximum number of pebbles on the graph at any step.
+    pub peak_memory: usize,
+    /// Cumulative complexity: sum of the pebbles on the graph at each step.
+    pub cumulative: usize,
+    /// Total number of pebbles placed.
+    pub placements: usize,
+}
+
+impl PebblingCost {
+    /// Energy complexity where storing a pebble for a step costs 1 and
+    /// placing one (computing the label) costs `core_ratio`.
+    pub fn energy(&self, core_ratio: usize) -> usize {
+        self.cumulative + core_ratio * self.placements
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PebblingError {
+    /// Node (placed or removed) at the given step that isn't in the graph.
+    OutOfRange { step: usize, node: Node },
+    /// Node placed at the given step while its parent wasn't pebbled.
+    MissingParent {
+        step: usize,
+        node: Node,
+        parent: Node,
+    },
+    /// Number of pebbles placed at the given step of a sequential pebbling.
+    NotSequential { step: usize, placed: usize },
+    /// Sink of the graph that was never pebbled.
+    Incomplete(Node),
+    /// Attack pebbling the nodes in intervals of 0 nodes.
+    EmptyInterval,
+}
+
+impl fmt::Display for PebblingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PebblingError::OutOfRange { step, node } => {
+                write!(f, "step {}: node {} is out of range", step, node)
+            }
+            PebblingError::MissingParent { step, node, parent } => write!(
+                f,
+                "step {}: node {} pebbled without its parent {}",
+                step, node, parent
+            ),
+            PebblingError::NotSequential { step, placed } => write!(
+                f,
+                "step {}: {} pebbles placed in a sequential pebbling",
+                step, placed
+            ),
+            PebblingError::Incomplete(node) => write!(f, "sink {} was never pebbled", node),
+            PebblingError::EmptyInterval => write!(f, "empty pebbling interval"),
+        }
+    }
+}
+
+impl error::Error for PebblingError {}
+
+/// Pebbling game over a graph: tracks the pebbles through the steps of a
+/// pebbling checking that each of them is legal and accounting its cost.
+pub struct Pebbling<'a, I = Node> {
+    graph: &'a Graph<I>,
+    game: PebblingGame,
+    pebbled: Vec<bool>,
+    // nodes that were pebbled at some point
+    visited: Vec<bool>,
+    count: usize,
+    cost: PebblingCost,
+}
+
+impl<'a, I: NodeIndex> Pebbling<'a, I> {
+    pub fn new(graph: &'a Graph<I>, game: PebblingGame) -> Self {
+        Pebbling {
+            graph,
+            game,
+            pebbled: vec![false; graph.size()],
+            visited: vec![false; graph.size()],
+            count: 0,
+            cost: PebblingCost::default(),
+        }
+    }
+
+    pub fn is_pebbled(&self, node: Node) -> bool {
+        self.pebbled[node]
+    }
+
+    /// Number of pebbles currently on the graph.
+    pub fn pebbles(&self) -> usize {
+        self.count
+    }
+
+    pub fn cost(&self) -> &PebblingCost {
+        &self.cost
+    }
+
+    /// Advance one step placing pebbles on the `placed` nodes, whose parents
+    /// must all be pebbled at the start of the step, and removing the ones
+    /// in `removed`. An illegal step leaves the pebbling untouched.
+    pub fn step(&mut self, placed: &[Node], removed: &[Node]) -> Result<(), PebblingError> {
+        let step = self.cost.time + 1;
+        let n = self.graph.size();
+        if let Some(&node) = placed.iter().chain(removed.iter()).find(|&&node| node >= n) {
+            return Err(PebblingError::OutOfRange { step, node });
+        }
+        let mut new: Vec<Node> = placed
+            .iter()
+            .cloned()
+            .filter(|&node| !self.pebbled[node])
+            .collect();
+        new.sort_unstable();
+        new.dedup();
+        if self.game == PebblingGame::Sequential && new.len() > 1 {
+            return Err(PebblingError::NotSequential {
+                step,
+                placed: new.len(),
+            });
+        }
+        for &node in new.iter() {
+            if let Some(parent) = self.graph.parents()[node]
+                .iter()
+                .map(|p| p.node())
+                .find(|&p| !self.pebbled[p])
+            {
+                return Err(PebblingError::MissingParent { step, node, parent });
+            }
+        }
+
+        for &node in new.iter() {
+            self.pebbled[node] = true;
+            self.visited[node] = true;
+        }
+        self.count += new.len();
+        for &node in removed.iter() {
+            if self.pebbled[node] {
+                self.pebbled[node] = false;
+                self.count -= 1;
+            }
+        }
+        self.cost.time += 1;
+        self.cost.placements += new.len();
+        self.cost.cumulative += self.count;
+        self.cost.peak_memory = std::cmp::max(self.cost.peak_memory, self.count);
+        Ok(())
+    }
+
+    /// Advance one step moving to the `configuration` of pebbles, placing
+    /// the ones that aren't on the graph and removing the ones missing.
+    pub fn apply(&mut self, configuration: &[Node]) -> Result<(), PebblingError> {
+        let step = self.cost.time + 1;
+        let mut next = vec![false; self.graph.size()];
+        for &node in configuration.iter() {
+            if node >= next.len() {
+                return Err(PebblingError::OutOfRange { step, node });
+            }
+            next[node] = true;
+        }
+        let placed: Vec<Node> = configuration
+            .iter()
+            .cloned()
+            .filter(|&node| !self.pebbled[node])
+            .collect();
+        let removed: Vec<Node> = (0..next.len())
+            .filter(|&node| self.pebbled[node] && !next[node])
+            .collect();
+        self.step(&placed, &removed)
+    }
+
+    /// Finish the pebbling checking that all the sinks of the graph were
+    /// pebbled at some step.
+    pub fn finish(self) -> Result<PebblingCost, PebblingError> {
+        let mut is_parent = vec![false; self.graph.size()];
+        self.graph
+            .parents()
+            .iter()
+            .flatten()
+            .for_each(|p| is_parent[p.node()] = true);
+        match (0..self.graph.size()).find(|&node| !is_parent[node] && !self.visited[node]) {
+            Some(sink) => Err(PebblingError::Incomplete(sink)),
+            None => Ok(self.cost),
+        }
+    }
+}
+
+/// Verify a pebbling `schedule` of `g`, given as the sequence of pebble
+/// configurations at the end of each step (starting from an empty graph),
+/// and return its cost.
+pub fn verify_schedule<I: NodeIndex>(
+    g: &Graph<I>,
+    game: PebblingGame,
+    schedule: &[Vec<Node>],
+) -> Result<PebblingCost, PebblingError> {
+    let mut pebbling = Pebbling::new(g, game);
+    for configuration in schedule.iter() {
+        pebbling.apply(configuration)?;
+    }
+    pebbling.finish()
+}
+
+/// Cost of the AB16 generic pebbling attack based on a depth-reducing set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttackCost {
+    pub cost: PebblingCost,
+    /// Length of the intervals of the light phases.
+    pub interval: usize,
+    /// Cumulative complexity of the naive sequential pebbling, `n(n+1)/2`.
+    pub naive_cumulative: usize,
+    /// `cost.cumulative / naive_cumulative`
+    pub ratio: f64,
+}
+
+impl fmt::Display for AttackCost {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "\t-> CC = {} ({:.4} of naive), peak memory = {}, time = {} (interval {})",
+            self.cost.cumulative, self.ratio, self.cost.peak_memory, self.cost.time, self.interval
+        )
+    }
+}
+
+/// Cumulative complexity of the naive sequential pebbling that pebbles each
+/// node in order and never removes any pebble.
+pub fn naive_cumulative<I: NodeIndex>(g: &Graph<I>) -> usize {
+    g.size() * (g.size() + 1) / 2
+}
+
+/// Default length of the light phase intervals balancing their cost (`~n*g`)
+/// with the one of the balloon phases (`~n*n*d/g`).
+pub fn default_interval(size: usize, depth: usize) -> usize {
+    let interval = ((size as f64) * (std::cmp::max(depth, 1) as f64)).sqrt() as usize;
+    std::cmp::max(std::cmp::min(interval, size), 1)
+}
+
+/// Simulate the generic parallel pebbling attack of Theorem 6 of the [AB16
+/// paper](https://eprint.iacr.org/2016/115.pdf) for a set `s` such that
+/// `depth(G-S) <= depth` and report its cost (see `ab16_pebbling_with`).
+pub fn ab16_pebbling<I: NodeIndex>(
+    g: &Graph<I>,
+    s: &ExclusionSet,
+    depth: usize,
+) -> Result<AttackCost, PebblingError> {
+    ab16_pebbling_with(g, s, default_interval(g.size(), depth))
+}
+
+/// The nodes are pebbled in intervals of `interval` nodes, keeping pebbles
+/// on the nodes of `s` at all times:
+/// * Balloon phase: all the parents of the next interval (not in `s`) that
+///   aren't pebbled are recomputed in parallel from their ancestors in G-S,
+///   in at most `depth(G-S) + 1` steps.
+/// * Light phase: the nodes of the interval are pebbled sequentially, only
+///   keeping the pebbles of the interval, of its parents and of `s`.
+// NOTE: The balloon phase doesn't overlap with the previous light phase
+// as in the paper, which adds at most `depth(G-S) + 1` steps per interval.
+pub fn ab16_pebbling_with<I: NodeIndex>(
+    g: &Graph<I>,
+    s: &ExclusionSet,
+    interval: usize,
+) -> Result<AttackCost, PebblingError> {
+    if interval == 0 {
+        return Err(PebblingError::EmptyInterval);
+    }
+    let n = g.size();
+    let mut pebbling = Pebbling::new(g, PebblingGame::Parallel);
+    // parents of the interval starting at `start` that are outside of it
+    // (and not in S, those are always pebbled)
+    let needed = |start: usize| -> Vec<Node> {
+        let mut parents: Vec<Node> = (start..std::cmp::min(start + interval, n))
+            .flat_map(|node| g.parents()[node].iter().map(|p| p.node()))
+            .filter(|&p| p < start && !s.contains(p))
+            .collect();
+        parents.sort_unstable();
+        parents.dedup();
+        parents
+    };
+
+    let mut pending_removal: Vec<Node> = Vec::new();
+    let mut needed_now = needed(0);
+    let mut in_balloon = vec![false; n];
+    for start in (0..n).step_by(interval) {
+        // balloon phase: ancestors in G-S of the missing parents
+        let mut balloon: Vec<Node> = Vec::new();
+        let mut tosearch: Vec<Node> = needed_now
+            .iter()
+            .cloned()
+            .filter(|&p| !pebbling.is_pebbled(p))
+            .collect();
+        tosearch.iter().for_each(|&p| in_balloon[p] = true);
+        while let Some(node) = tosearch.pop() {
+            balloon.push(node);
+            for p in g.parents()[node].iter().map(|p| p.node()) {
+                if !s.contains(p) && !pebbling.is_pebbled(p) && !in_balloon[p] {
+                    in_balloon[p] = true;
+                    tosearch.push(p);
+                }
+            }
+        }
+        balloon.sort_unstable();
+        // layer of each node in the balloon (nodes are in topological order)
+        let mut layers: Vec<Vec<Node>> = Vec::new();
+        let mut layer_of = std::collections::HashMap::with_capacity(balloon.len());
+        for &node in balloon.iter() {
+            let layer = g.parents()[node]
+                .iter()
+                .filter_map(|p| layer_of.get(&p.node()).map(|l| l + 1))
+                .max()
+                .unwrap_or(0);
+            layer_of.insert(node, layer);
+            if layers.len() <= layer {
+                layers.push(vec![]);
+            }
+            layers[layer].push(node);
+        }
+        for layer in layers.iter() {
+            pebbling.step(layer, &pending_removal)?;
+            pending_removal.clear();
+        }
+        balloon.iter().for_each(|&p| in_balloon[p] = false);
+        // only the needed parents are kept for the light phase
+        pending_removal.extend(
+            balloon
+                .into_iter()
+                .filter(|p| needed_now.binary_search(p).is_err()),
+        );
+
+        // light phase
+        let end = std::cmp::min(start + interval, n);
+        for node in start..end {
+            pebbling.step(&[node], &pending_removal)?;
+            pending_removal.clear();
+        }
+        let needed_next = needed(end);
+        pending_removal.extend(
+            needed_now
+                .iter()
+                .cloned()
+                .chain(start..end)
+                .filter(|&p| !s.contains(p) && needed_next.binary_search(&p).is_err()),
+        );
+        needed_now = needed_next;
+    }
+
+    let naive = naive_cumulative(g);
+    let cost = pebbling.finish()?;
+    Ok(AttackCost {
+        ratio: cost.cumulative as f64 / naive as f64,
+        naive_cumulative: naive,
+        interval,
+        cost,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::attacks::{depth_reduce, AttackAlgo};
+    use crate::graph::{self, DRGAlgo, GraphSpec};
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    #[test]
+    fn pebbling_legality() {
+        // 0 -> 1 -> 3, 0 -> 2 -> 3
+        let g = graph::tests::graph_from(vec![vec![], vec![0], vec![0], vec![1, 2]]);
+        let parallel = vec![vec![0], vec![0, 1, 2], vec![1, 2, 3], vec![3]];
+        let cost = verify_schedule(&g, PebblingGame::Parallel, &parallel).unwrap();
+        assert_eq!(
+            cost,
+            PebblingCost {
+                time: 4,
+                peak_memory: 3,
+                cumulative: 1 + 3 + 3 + 1,
+                placements: 4,
+            }
+        );
+        assert_eq!(cost.energy(10), 8 + 40);
+        assert_eq!(
+            verify_schedule(&g, PebblingGame::Sequential, &parallel),
+            Err(PebblingError::NotSequential { step: 2, placed: 2 })
+        );
+
+        let sequential = vec![vec![0], vec![0, 1], vec![0, 1, 2], vec![1, 2, 3]];
+        let cost = verify_schedule(&g, PebblingGame::Sequential, &sequential).unwrap();
+        assert_eq!(cost.cumulative, 1 + 2 + 3 + 3);
+
+        // 3 pebbled after removing its parent 2
+        let illegal = vec![vec![0], vec![0, 1, 2], vec![1], vec![1, 3]];
+        assert_eq!(
+            verify_schedule(&g, PebblingGame::Parallel, &illegal),
+            Err(PebblingError::MissingParent {
+                step: 4,
+                node: 3,
+                parent: 2
+            })
+        );
+        assert_eq!(
+            verify_schedule(&g, PebblingGame::Parallel, &parallel[..2]),
+            Err(PebblingError::Incomplete(3))
+        );
+        assert_eq!(
+            verify_schedule(&g, PebblingGame::Parallel, &[vec![4]]),
+            Err(PebblingError::OutOfRange { step: 1, node: 4 })
+        );
+    }
+
+    #[test]
+    fn ab16_line() {
+        // line graph 0 -> 1 -> ... -> 7, with S = {3} and intervals of 4
+        // nodes the second interval only needs node 3
+        let parents = (0..8)
+            .map(|n| if n == 0 { vec![] } else { vec![n - 1] })
+            .collect();
+        let g = graph::tests::graph_from(parents);
+        let s = ExclusionSet::from_nodes(&g, vec![3]);
+        let cost = ab16_pebbling_with(&g, &s, 4).unwrap();
+        // 8 light steps with no balloon since 3 is always pebbled
+        assert_eq!(cost.cost.time, 8);
+        assert_eq!(cost.cost.placements, 8);
+        // [0], [0,1], [0,1,2], [0,1,2,3], [3,4], [3,4,5], [3,4,5,6], [3,...,7]
+        assert_eq!(cost.cost.cumulative, 1 + 2 + 3 + 4 + 2 + 3 + 4 + 5);
+        assert_eq!(cost.cost.peak_memory, 5);
+        assert_eq!(cost.naive_cumulative, 36);
+
+        // without S node 3 is still kept since the next interval needs it
+        let cost = ab16_pebbling_with(&g, &ExclusionSet::new(&g), 4).unwrap();
+        assert_eq!(cost.cost.placements, 8);
+
+        assert_eq!(
+            ab16_pebbling_with(&g, &s, 0).unwrap_err(),
+            PebblingError::EmptyInterval
+        );
+    }
+
+    #[test]
+    fn ab16_attack_cost() {
+        let size = 1 << 10;
+        let g = Graph::new(size, graph::tests::TEST_SEED, DRGAlgo::MetaBucket(3)).unwrap();
+        let depth = size / 16;
+        let s = depth_reduce(&g, AttackAlgo::ValiantDepth(depth)).unwrap();
+        let cost = ab16_pebbling(&g, &s, g.depth_exclude(&s)).unwrap();
+        // the sink is pebbled and the attack beats the naive pebbling
+        assert!(cost.cost.time >= size);
+        assert!(cost.ratio < 1.0);
+        assert!(cost.cost.peak_memory < size);
+
+        // same pebbling of the graph stored with u32 nodes
+        let spec = GraphSpec {
+            size,
+            seed: graph::tests::TEST_SEED,
+            algo: DRGAlgo::MetaBucket(3),
+        };
+        let g32 = Graph::<u32>::generate(spec, &mut ChaChaRng::from_seed(spec.seed)).unwrap();
+        let cost32 = ab16_pebbling(&g32, &s, g.depth_exclude(&s)).unwrap();
+        assert_eq!(cost32.cost, cost.cost);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::attacks::AttackAlgo;
+use crate::error::Error;
 use crate::graph::{DRGAlgo, GraphSpec};
 use csv;
 use serde::{Deserialize, Serialize};
@@ -65,6 +66,10 @@ pub struct AttackResults {
     pub runs: usize,
     pub attack: AttackAlgo,
     pub results: Vec<AveragedAttackResult>,
+    /// Curve of each run if the profile records them (see
+    /// `AttackProfile::curve`), empty otherwise.
+    #[serde(default)]
+    pub curves: Vec<Curve>,
 }
 
 impl std::fmt::Display for SingleAttackResult {
@@ -78,7 +83,7 @@ impl std::fmt::Display for SingleAttackResult {
 }
 
 impl AttackResults {
-    pub fn to_csv<W: Write>(&self, w: W) -> Result<(), csv::Error> {
+    pub fn to_csv<W: Write>(&self, w: W) -> Result<(), Error> {
         #[derive(Serialize)]
         struct Record {
             graph_size: u32,
@@ -98,22 +103,32 @@ impl AttackResults {
             AttackAlgo::ValiantSize(_) => ("valiant", "alpha"),
             // beta stays the same
             AttackAlgo::ValiantDepth(_) => ("valiant", "beta"),
+            // the target is already the size of G-S
+            AttackAlgo::ValiantAB16(_) => ("valiant-ab16", "alpha"),
             AttackAlgo::GreedySize(_, _) => ("greedy", "alpha"),
             AttackAlgo::GreedyDepth(_, _) => ("greedy", "beta"),
-            _ => panic!("unknown type of attack to serialize into csv"),
+            AttackAlgo::AnnealingSize(_, _) => ("annealing", "alpha"),
+            AttackAlgo::AnnealingDepth(_, _) => ("annealing", "beta"),
+            AttackAlgo::EvolutionSize(_, _) => ("evolution", "alpha"),
+            AttackAlgo::EvolutionDepth(_, _) => ("evolution", "beta"),
+            AttackAlgo::HybridDepth(_, _) => ("hybrid", "beta"),
         };
         let (graph_type, degree) = match self.spec.algo {
             DRGAlgo::BucketSample => ("bucket", 2),
             DRGAlgo::MetaBucket(d) => ("meta-bucket", d),
             DRGAlgo::Ren21(d) => ("ren21", d),
             DRGAlgo::KConnector(k) => ("Kconnector", k),
+            DRGAlgo::PowerLaw(d, _) => ("power-law", d),
+            DRGAlgo::Imported => ("imported", 0),
+            DRGAlgo::Custom(d) => ("custom", d),
         };
         let truncate = |before: f64| (before * 100.0).floor() / 100.0;
-        self.results.iter().try_for_each(|r| {
+        self.results.iter().try_for_each(|r| -> Result<(), Error> {
             // mean size is the size of the set we remove
             let alpha = 1.0 - r.mean_size;
-            let target = match target_type {
-                "alpha" => 1.0 - r.target,
+            let target = match (&self.attack, target_type) {
+                (AttackAlgo::ValiantAB16(_), _) => r.target,
+                (_, "alpha") => 1.0 - r.target,
                 _ => r.target,
             };
             wtr.serialize(Record {
@@ -125,7 +140,73 @@ impl AttackResults {
                 target: truncate(target),
                 alpha: truncate(alpha),
                 beta: truncate(r.mean_depth),
-            })
+            })?;
+            Ok(())
         })
     }
+
+    /// Write the points of the curves of all the runs, along with their run.
+    pub fn curves_to_csv<W: Write>(&self, w: W) -> Result<(), Error> {
+        #[derive(Serialize)]
+        struct Record {
+            run: usize,
+            iteration: usize,
+            size: usize,
+            depth: usize,
+            elapsed: f64,
+        }
+        let mut wtr = csv::Writer::from_writer(w);
+        for (run, curve) in self.curves.iter().enumerate() {
+            for point in curve.points.iter() {
+                wtr.serialize(Record {
+                    run,
+                    iteration: point.iteration,
+                    size: point.size,
+                    depth: point.depth,
+                    elapsed: point.elapsed,
+                })?;
+            }
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    pub fn curves_to_json<W: Write>(&self, w: W) -> Result<(), Error> {
+        serde_json::to_writer_pretty(w, &self.curves)?;
+        Ok(())
+    }
+}
+
+/// Point of the curve of an attack: |S| and depth(G-S) after `iteration`
+/// steps of the attack (the first point is the empty set), `elapsed`
+/// seconds after it started.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CurvePoint {
+    pub iteration: usize,
+    pub size: usize,
+    pub depth: usize,
+    pub elapsed: f64,
+}
+
+/// Depth/size trade-off curve of a single run of an attack, with a point
+/// per iteration (see `attacks::depth_reduce_with_curve`).
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Curve {
+    pub points: Vec<CurvePoint>,
+}
+
+impl Curve {
+    pub fn to_csv<W: Write>(&self, w: W) -> Result<(), Error> {
+        let mut wtr = csv::Writer::from_writer(w);
+        self.points
+            .iter()
+            .try_for_each(|point| wtr.serialize(point))?;
+        wtr.flush()?;
+        Ok(())
+    }
+
+    pub fn to_json<W: Write>(&self, w: W) -> Result<(), Error> {
+        serde_json::to_writer_pretty(w, self)?;
+        Ok(())
+    }
 }
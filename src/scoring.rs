@@ -0,0 +1,378 @@
+use std::cmp::Reverse;
+
+use serde::{Deserialize, Serialize};
+
+use crate::attacks::{count_paths_among, count_paths_degree_among, is_ranked, GreedyParams, Pair};
+use crate::graph::{ExclusionSet, Graph, Node, NodeIndex};
+
+// Scores in [0, 1] are scaled by this factor to fit in a `Pair`.
+const SCORE_SCALE: f64 = 1e12;
+
+/// Ranking of the nodes of G-S used by Greedy to pick the ones to remove
+/// next: it returns the nodes of G-S (and only those) with their score, the
+/// highest first. The Greedy loop only relies on that order (and on the
+/// highest score when `radius` is 0), so any scorer can be plugged into it
+/// through `greedy_reduce_with`.
+pub trait NodeScorer<I: NodeIndex = Node>: Sync {
+    fn rank(&self, g: &Graph<I>, s: &ExclusionSet, p: &GreedyParams) -> Vec<Pair>;
+
+    /// Same as `rank` only returning the `candidates` (nodes of G-S), used by
+    /// `GreedyParams::critical`. The built-in scorers don't score the other
+    /// nodes, the default filters the whole ranking.
+    fn rank_candidates(
+        &self,
+        g: &Graph<I>,
+        s: &ExclusionSet,
+        p: &GreedyParams,
+        candidates: &ExclusionSet,
+    ) -> Vec<Pair> {
+        self.rank(g, s, p)
+            .into_iter()
+            .filter(|pair| candidates.contains(pair.0))
+            .collect()
+    }
+}
+
+// Implement `NodeScorer` for a built-in scorer with a `rank_among` method
+// taking the optional candidates.
+macro_rules! node_scorer {
+    ($($t:ty),*) => {$(
+        impl<I: NodeIndex> NodeScorer<I> for $t {
+            fn rank(&self, g: &Graph<I>, s: &ExclusionSet, p: &GreedyParams) -> Vec<Pair> {
+                self.rank_among(g, s, p, None)
+            }
+
+            fn rank_candidates(
+                &self,
+                g: &Graph<I>,
+                s: &ExclusionSet,
+                p: &GreedyParams,
+                candidates: &ExclusionSet,
+            ) -> Vec<Pair> {
+                self.rank_among(g, s, p, Some(candidates))
+            }
+        }
+    )*};
+}
+
+node_scorer!(
+    PathsScorer,
+    DegreeScorer,
+    DepthScorer,
+    BetweennessScorer,
+    PageRankScorer
+);
+
+/// Built-in scorers, selected by `GreedyParams::scoring`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub enum Scoring {
+    /// Incident paths of `length` edges (`count_paths`, which also honors
+    /// `use_degree` and `sampling`).
+    #[default]
+    Paths,
+    /// Degree in G-S.
+    Degree,
+    /// Length of the longest path through the node (see `DepthScorer`).
+    DepthContribution,
+    /// Number of longest paths through the node (see `BetweennessScorer`).
+    Betweenness { slack: usize },
+    /// Stationary distribution of a random walk (see `PageRankScorer`).
+    PageRank { damping: f64, iterations: usize },
+}
+
+impl Scoring {
+    pub fn scorer<I: NodeIndex>(&self) -> Box<dyn NodeScorer<I>> {
+        match *self {
+            Scoring::Paths => Box::new(PathsScorer),
+            Scoring::Degree => Box::new(DegreeScorer),
+            Scoring::DepthContribution => Box::new(DepthScorer),
+            Scoring::Betweenness { slack } => Box::new(BetweennessScorer { slack }),
+            Scoring::PageRank {
+                damping,
+                iterations,
+            } => Box::new(PageRankScorer {
+                damping,
+                iterations,
+            }),
+        }
+    }
+}
+
+pub struct PathsScorer;
+
+impl PathsScorer {
+    fn rank_among<I: NodeIndex>(
+        &self,
+        g: &Graph<I>,
+        s: &ExclusionSet,
+        p: &GreedyParams,
+        candidates: Option<&ExclusionSet>,
+    ) -> Vec<Pair> {
+        count_paths_among(g, s, p, candidates)
+    }
+}
+
+pub struct DegreeScorer;
+
+impl DegreeScorer {
+    fn rank_among<I: NodeIndex>(
+        &self,
+        g: &Graph<I>,
+        s: &ExclusionSet,
+        _: &GreedyParams,
+        candidates: Option<&ExclusionSet>,
+    ) -> Vec<Pair> {
+        count_paths_degree_among(g, s, candidates)
+    }
+}
+
+/// Scores a node by the length of the longest path of G-S going through it
+/// (its forward plus backward depth), so the nodes of the longest paths come
+/// first. Ties are broken in favor of the nodes closer to the middle of their
+/// path, which split it in the most even halves.
+pub struct DepthScorer;
+
+impl DepthScorer {
+    fn rank_among<I: NodeIndex>(
+        &self,
+        g: &Graph<I>,
+        s: &ExclusionSet,
+        _: &GreedyParams,
+        candidates: Option<&ExclusionSet>,
+    ) -> Vec<Pair> {
+        let (forward, backward) = g.depths_exclude(s);
+        sorted(
+            (0..g.size())
+                .filter(|&node| is_ranked(s, candidates, node))
+                .map(|node| {
+                    let through = forward[node] + backward[node];
+                    let middle = std::cmp::min(forward[node], backward[node]);
+                    Pair(node, through * g.size() + middle)
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Approximate betweenness restricted to the longest-path DAG: scores a
+/// node by the number of paths through it that are the longest ones ending
+/// and starting at it, only for the nodes whose longest path is within
+/// `slack` of the depth of G-S (the others score 0). With no slack this is
+/// the number of longest paths of G-S each node lies on.
+pub struct BetweennessScorer {
+    pub slack: usize,
+}
+
+impl BetweennessScorer {
+    fn rank_among<I: NodeIndex>(
+        &self,
+        g: &Graph<I>,
+        s: &ExclusionSet,
+        _: &GreedyParams,
+        candidates: Option<&ExclusionSet>,
+    ) -> Vec<Pair> {
+        let (forward, backward) = g.depths_exclude(s);
+        let depth = forward.iter().cloned().max().unwrap_or(0);
+        // number of longest paths ending and starting at each node, as floats
+        // since they grow exponentially with the depth
+        let mut ending = vec![0.0; g.size()];
+        let mut starting = vec![0.0; g.size()];
+        for (node, parents) in g.parents().iter().enumerate() {
+            if s.contains(node) {
+                continue;
+            }
+            ending[node] = if forward[node] == 0 {
+                1.0
+            } else {
+                parents
+                    .iter()
+                    .map(|p| p.node())
+                    .filter(|&p| !s.contains(p) && forward[p] + 1 == forward[node])
+                    .map(|p| ending[p])
+                    .sum()
+            };
+        }
+        for (node, parents) in g.parents().iter().enumerate().rev() {
+            if s.contains(node) {
+                continue;
+            }
+            if backward[node] == 0 {
+                starting[node] = 1.0;
+            }
+            for p in parents.iter().map(|p| p.node()) {
+                if !s.contains(p) && backward[p] == backward[node] + 1 {
+                    starting[p] += starting[node];
+                }
+            }
+        }
+        let scores = (0..g.size())
+            .map(|node| {
+                if forward[node] + backward[node] + self.slack >= depth {
+                    ending[node] * starting[node]
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+        scaled(s, candidates, scores)
+    }
+}
+
+/// PageRank-like score: the stationary distribution of a random walk over
+/// the edges of G-S taken in both directions, restarting at a uniform node
+/// with probability `1 - damping`, approximated with a fixed number of
+/// iterations. Nodes connecting many well-connected nodes score high.
+pub struct PageRankScorer {
+    pub damping: f64,
+    pub iterations: usize,
+}
+
+impl PageRankScorer {
+    fn rank_among<I: NodeIndex>(
+        &self,
+        g: &Graph<I>,
+        s: &ExclusionSet,
+        _: &GreedyParams,
+        candidates: Option<&ExclusionSet>,
+    ) -> Vec<Pair> {
+        let alive = g.size() - s.size();
+        if alive == 0 {
+            return Vec::new();
+        }
+        let mut degree = vec![0; g.size()];
+        g.for_each_edge(|e| {
+            let (parent, child) = (e.parent.node(), e.child.node());
+            if !s.contains(parent) && !s.contains(child) {
+                degree[parent] += 1;
+                degree[child] += 1;
+            }
+        });
+        let restart = (1.0 - self.damping) / alive as f64;
+        let mut scores: Vec<f64> = (0..g.size())
+            .map(|node| {
+                if s.contains(node) {
+                    0.0
+                } else {
+                    1.0 / alive as f64
+                }
+            })
+            .collect();
+        for _ in 0..self.iterations {
+            let mut next: Vec<f64> = (0..g.size())
+                .map(|node| if s.contains(node) { 0.0 } else { restart })
+                .collect();
+            g.for_each_edge(|e| {
+                let (parent, child) = (e.parent.node(), e.child.node());
+                if !s.contains(parent) && !s.contains(child) {
+                    next[child] += self.damping * scores[parent] / degree[parent] as f64;
+                    next[parent] += self.damping * scores[child] / degree[child] as f64;
+                }
+            });
+            scores = next;
+        }
+        scaled(s, candidates, scores)
+    }
+}
+
+// Ranks the nodes of G-S by their `scores`, scaled relative to the highest
+// one.
+fn scaled(s: &ExclusionSet, candidates: Option<&ExclusionSet>, scores: Vec<f64>) -> Vec<Pair> {
+    let max = scores.iter().cloned().fold(0.0, f64::max);
+    let factor = if max > 0.0 { SCORE_SCALE / max } else { 0.0 };
+    sorted(
+        scores
+            .into_iter()
+            .enumerate()
+            .filter(|&(node, _)| is_ranked(s, candidates, node))
+            .map(|(node, score)| Pair(node, (score * factor).round() as usize))
+            .collect(),
+    )
+}
+
+fn sorted(mut incidents: Vec<Pair>) -> Vec<Pair> {
+    incidents.sort_by_key(|pair| Reverse(pair.1));
+    incidents
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::attacks::{depth_reduce, greedy_reduce_with, AttackAlgo};
+    use crate::graph::{self, DRGAlgo};
+
+    #[test]
+    fn scoring_rankings() {
+        // 0->1->2->3->4 with a shortcut 0->2 and a lonely edge 5->6
+        let g = graph::tests::graph_from(vec![
+            vec![],
+            vec![0],
+            vec![0, 1],
+            vec![2],
+            vec![3],
+            vec![],
+            vec![5],
+        ]);
+        let s = ExclusionSet::new(&g);
+        let p = GreedyParams::default();
+
+        // the middle of the longest path first, the lonely edge last
+        let depth = DepthScorer.rank(&g, &s, &p);
+        assert_eq!(depth[0].0, 2);
+        assert!(depth[5..].iter().all(|pair| pair.0 == 5 || pair.0 == 6));
+
+        // a single longest path, 0->1->2->3->4
+        let betweenness = BetweennessScorer { slack: 0 }.rank(&g, &s, &p);
+        assert_eq!(betweenness.len(), 7);
+        let mut top: Vec<usize> = betweenness[..5].iter().map(|pair| pair.0).collect();
+        top.sort();
+        assert_eq!(top, vec![0, 1, 2, 3, 4]);
+        assert!(betweenness[5..].iter().all(|pair| pair.1 == 0));
+
+        let pagerank = PageRankScorer {
+            damping: 0.85,
+            iterations: 50,
+        }
+        .rank(&g, &ExclusionSet::from_nodes(&g, vec![6]), &p);
+        assert_eq!(pagerank.len(), 6);
+        assert!(pagerank.iter().all(|pair| pair.0 != 6));
+        // the only node of degree 3
+        assert_eq!(pagerank[0].0, 2);
+    }
+
+    #[test]
+    fn scoring_greedy() {
+        let size = 512;
+        let depth = size / 4;
+        let g = Graph::new(size, graph::tests::TEST_SEED, DRGAlgo::MetaBucket(3)).unwrap();
+        let p = GreedyParams {
+            k: 8,
+            radius: 2,
+            length: 8,
+            reset: true,
+            iter_topk: true,
+            ..GreedyParams::default()
+        };
+        // the default scorer is the original Greedy
+        let paths = depth_reduce(&g, AttackAlgo::GreedyDepth(depth, p.clone())).unwrap();
+        let with = greedy_reduce_with(&g, AttackAlgo::GreedyDepth(depth, p.clone()), &PathsScorer)
+            .unwrap();
+        assert_eq!(paths, with);
+
+        for scoring in vec![
+            Scoring::Degree,
+            Scoring::DepthContribution,
+            Scoring::Betweenness { slack: 2 },
+            Scoring::PageRank {
+                damping: 0.85,
+                iterations: 20,
+            },
+        ] {
+            let p = GreedyParams {
+                scoring,
+                ..p.clone()
+            };
+            let s = depth_reduce(&g, AttackAlgo::GreedyDepth(depth, p)).unwrap();
+            assert!(g.depth_exclude(&s) <= depth);
+        }
+    }
+}
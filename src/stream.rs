@@ -0,0 +1,429 @@
+use std::error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use rand::SeedableRng;
+use rand_chacha::ChaChaRng;
+use rayon::prelude::*;
+
+use crate::graph::{
+    read_varint, remove_duplicate, write_varint, DRGAlgo, Edge, ExclusionSet, ExclusionSetError,
+    Graph, GraphSpec, MetaBucketSampler, Node, ParentSampler, PowerLawSampler, Ren21Sampler,
+};
+use crate::utils;
+
+const MAGIC: &[u8; 4] = b"DRGP";
+const VERSION: u8 = 1;
+
+/// Error of the streaming generation and consumption of graphs.
+#[derive(Debug)]
+pub enum StreamError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    /// The input doesn't follow the binary format.
+    Format(String),
+    /// The graph can't be generated from its spec.
+    Unsupported(DRGAlgo),
+    /// The graph has too many nodes to store their depths as `u32`.
+    TooLarge(usize),
+    /// The exclusion set doesn't match the graph.
+    ExclusionSet(ExclusionSetError),
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StreamError::Io(e) => write!(f, "io error: {}", e),
+            StreamError::Json(e) => write!(f, "json error: {}", e),
+            StreamError::Format(msg) => write!(f, "invalid graph stream: {}", msg),
+            StreamError::Unsupported(algo) => write!(f, "can't generate {:?} graphs", algo),
+            StreamError::ExclusionSet(e) => write!(f, "{}", e),
+            StreamError::TooLarge(size) => {
+                write!(
+                    f,
+                    "graph of {} nodes too big to store the depths as u32",
+                    size
+                )
+            }
+        }
+    }
+}
+
+impl error::Error for StreamError {}
+
+impl From<io::Error> for StreamError {
+    fn from(e: io::Error) -> Self {
+        StreamError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for StreamError {
+    fn from(e: serde_json::Error) -> Self {
+        StreamError::Json(e)
+    }
+}
+
+/// Generate the graph of `spec` and write it to `w` in our binary format,
+/// `chunk` nodes at a time (generated in parallel), so only one chunk of
+/// parents is in memory at any time. The graph is the same `Graph::new`
+/// generates from the same spec (up to the order of the parents of each
+/// node), which allows generating graphs that don't fit in memory.
+///
+/// The format is the magic `DRGP`, a version byte, the length of the JSON
+/// encoding of the `GraphSpec` (without the seed) followed by it, and then
+/// for each node in order the number of its parents and the distance to each
+/// of them, all of them as LEB128 varints (~1 byte per edge for short
+/// distances, at most ~4 for 2^30 nodes).
+pub fn generate<W: Write>(spec: GraphSpec, chunk: usize, w: W) -> Result<(), StreamError> {
+    assert!(chunk > 0, "empty chunks");
+    let mut w = BufWriter::new(w);
+    let header = serde_json::to_vec(&spec)?;
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(MAGIC);
+    bytes.push(VERSION);
+    write_varint(&mut bytes, header.len());
+    bytes.extend_from_slice(&header);
+    w.write_all(&bytes)?;
+
+    // only used (sequentially) by `BucketSample`
+    let mut rng = ChaChaRng::from_seed(spec.seed);
+    for start in (0..spec.size).step_by(chunk) {
+        let end = std::cmp::min(start + chunk, spec.size);
+        let parents: Vec<Vec<Node>> = match spec.algo {
+            DRGAlgo::BucketSample => (start..end)
+                .map(|node| Graph::bucket_sample_parents(node, &mut rng))
+                .collect(),
+            DRGAlgo::MetaBucket(degree) => {
+                sample_chunk(&spec, start..end, &MetaBucketSampler { degree })
+            }
+            DRGAlgo::Ren21(degree) => sample_chunk(&spec, start..end, &Ren21Sampler { degree }),
+            DRGAlgo::PowerLaw(degree, exponent) => {
+                sample_chunk(&spec, start..end, &PowerLawSampler { degree, exponent })
+            }
+            DRGAlgo::KConnector(k) => (start..end)
+                .map(|node| (node.saturating_sub(k)..node).collect())
+                .collect(),
+            DRGAlgo::Imported | DRGAlgo::Custom(_) => {
+                return Err(StreamError::Unsupported(spec.algo))
+            }
+        };
+
+        bytes.clear();
+        for (node, mut parents) in (start..end).zip(parents) {
+            remove_duplicate(&mut parents);
+            write_varint(&mut bytes, parents.len());
+            parents
+                .iter()
+                .for_each(|&parent| write_varint(&mut bytes, node - parent));
+        }
+        w.write_all(&bytes)?;
+    }
+    w.flush()?;
+    Ok(())
+}
+
+fn sample_chunk<S: ParentSampler>(
+    spec: &GraphSpec,
+    nodes: std::ops::Range<Node>,
+    sampler: &S,
+) -> Vec<Vec<Node>> {
+    nodes
+        .into_par_iter()
+        .map(|node| Graph::sampled_parents(&spec.seed, node, sampler))
+        .collect()
+}
+
+/// Write the graph of `spec` to the file at `path` (see `generate`).
+pub fn generate_file<P: AsRef<Path>>(
+    spec: GraphSpec,
+    chunk: usize,
+    path: P,
+) -> Result<(), StreamError> {
+    generate(spec, chunk, File::create(path)?)
+}
+
+/// Parents of consecutive nodes, along with the first of them.
+pub type Chunk = (Node, Vec<Vec<Node>>);
+
+/// Reader of a graph in our binary format (see `generate`), returning the
+/// parents of its nodes in chunks in topological order.
+pub struct ParentsReader<R: Read> {
+    r: BufReader<R>,
+    spec: GraphSpec,
+    // next node to read
+    next: Node,
+}
+
+impl<R: Read> ParentsReader<R> {
+    pub fn new(r: R) -> Result<Self, StreamError> {
+        let mut r = BufReader::new(r);
+        let mut magic = [0; 5];
+        r.read_exact(&mut magic)?;
+        if &magic[..4] != MAGIC || magic[4] != VERSION {
+            return Err(StreamError::Format("unknown magic or version".into()));
+        }
+        let len = read_varint(&mut r)?;
+        // read through `take` rather than preallocated from the (untrusted) length
+        let mut header = Vec::new();
+        (&mut r).take(len as u64).read_to_end(&mut header)?;
+        if header.len() != len {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        let spec = serde_json::from_slice(&header)?;
+        Ok(ParentsReader { r, spec, next: 0 })
+    }
+
+    /// Spec of the graph (without its seed).
+    pub fn spec(&self) -> &GraphSpec {
+        &self.spec
+    }
+
+    /// Read the parents of (at most) the next `chunk` nodes, `None` after
+    /// the last node.
+    pub fn next_chunk(&mut self, chunk: usize) -> Result<Option<Chunk>, StreamError> {
+        let start = self.next;
+        let end = std::cmp::min(start.saturating_add(chunk), self.spec.size);
+        if start >= end {
+            return Ok(None);
+        }
+        let mut parents = Vec::with_capacity(end - start);
+        for node in start..end {
+            let count = read_varint(&mut self.r)?;
+            // the parents are distinct and smaller than the node, which also
+            // bounds the allocation by the bytes read so far
+            if count > node {
+                return Err(StreamError::Format(format!(
+                    "{} parents for node {}",
+                    count, node
+                )));
+            }
+            let mut node_parents = Vec::with_capacity(count);
+            for _ in 0..count {
+                let distance = read_varint(&mut self.r)?;
+                if distance == 0 || distance > node {
+                    return Err(StreamError::Format(format!(
+                        "invalid parent distance {} for node {}",
+                        distance, node
+                    )));
+                }
+                node_parents.push(node - distance);
+            }
+            parents.push(node_parents);
+        }
+        self.next = end;
+        Ok(Some((start, parents)))
+    }
+
+    /// Read the whole graph into memory.
+    pub fn read_graph(mut self) -> Result<Graph, StreamError> {
+        // not preallocated from the (untrusted) size of the header
+        let mut parents = Vec::new();
+        while let Some((_, chunk)) = self.next_chunk(1 << 16)? {
+            parents.extend(chunk);
+        }
+        Ok(Graph::from_parents(self.spec, parents))
+    }
+}
+
+impl ParentsReader<File> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, StreamError> {
+        Self::new(File::open(path)?)
+    }
+}
+
+/// Depth of G-S (the whole graph if `exclude` is `None`) reading the graph
+/// `chunk` nodes at a time: besides the chunk only the depth of each node is
+/// kept in memory, as a `u32` (4 GiB for 2^30 nodes).
+pub fn stream_depth<R: Read>(
+    mut reader: ParentsReader<R>,
+    chunk: usize,
+    exclude: Option<&ExclusionSet>,
+) -> Result<usize, StreamError> {
+    let size = reader.spec().size;
+    if size > u32::MAX as usize {
+        return Err(StreamError::TooLarge(size));
+    }
+    if let Some(set) = exclude.filter(|set| set.len() != size) {
+        return Err(StreamError::ExclusionSet(ExclusionSetError::Len {
+            expected: size,
+            len: set.len(),
+        }));
+    }
+    let excluded = |node: Node| exclude.is_some_and(|s| s.contains(node));
+    // grown as the nodes are read, not preallocated from the header
+    let mut depths: Vec<u32> = Vec::new();
+    while let Some((start, parents)) = reader.next_chunk(chunk)? {
+        for (node, node_parents) in (start..).zip(parents) {
+            let depth = if excluded(node) {
+                0
+            } else {
+                node_parents
+                    .iter()
+                    .filter(|&&p| !excluded(p))
+                    .map(|&p| depths[p] + 1)
+                    .max()
+                    .unwrap_or(0)
+            };
+            depths.push(depth);
+        }
+    }
+    Ok(depths.into_iter().max().unwrap_or(0) as usize)
+}
+
+/// Number of edges in each of the Valiant partitions (edges grouped by the
+/// most significant bit in which their nodes differ), reading the graph
+/// `chunk` nodes at a time in constant memory.
+pub fn stream_partition_counts<R: Read>(
+    mut reader: ParentsReader<R>,
+    chunk: usize,
+) -> Result<Vec<usize>, StreamError> {
+    let mut counts = vec![0; utils::node_bitsize::<Node>()];
+    while let Some((start, parents)) = reader.next_chunk(chunk)? {
+        for (node, node_parents) in (start..).zip(parents) {
+            for &parent in node_parents.iter() {
+                counts[utils::msbd(&Edge::new(parent, node))] += 1;
+            }
+        }
+    }
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::attacks::valiant_partitions;
+    use crate::graph;
+
+    fn sorted_parents(g: &Graph) -> Vec<Vec<Node>> {
+        g.parents()
+            .iter()
+            .map(|parents| {
+                let mut parents = parents.clone();
+                parents.sort();
+                parents
+            })
+            .collect()
+    }
+
+    #[test]
+    fn stream_same_graph() {
+        let size = 1000;
+        for &algo in [
+            DRGAlgo::BucketSample,
+            DRGAlgo::MetaBucket(3),
+            DRGAlgo::Ren21(4),
+            DRGAlgo::PowerLaw(4, 1.5),
+            DRGAlgo::KConnector(3),
+        ]
+        .iter()
+        {
+            let spec = GraphSpec {
+                size,
+                seed: graph::tests::TEST_SEED,
+                algo,
+            };
+            let mut bytes = Vec::new();
+            generate(spec, 128, &mut bytes).unwrap();
+            let g = Graph::new(size, graph::tests::TEST_SEED, algo).unwrap();
+            let streamed = ParentsReader::new(&bytes[..])
+                .unwrap()
+                .read_graph()
+                .unwrap();
+            assert_eq!(streamed.size(), size);
+            assert_eq!(sorted_parents(&streamed), sorted_parents(&g));
+
+            // any chunk size gives the same results
+            for &chunk in [1, 100, size].iter() {
+                let reader = || ParentsReader::new(&bytes[..]).unwrap();
+                assert_eq!(stream_depth(reader(), chunk, None).unwrap(), g.depth());
+                let s = ExclusionSet::from_nodes(&g, (0..size).step_by(7).collect());
+                assert_eq!(
+                    stream_depth(reader(), chunk, Some(&s)).unwrap(),
+                    g.depth_exclude(&s)
+                );
+                let counts: Vec<usize> = valiant_partitions(&g)
+                    .iter()
+                    .map(|partition| partition.len())
+                    .collect();
+                assert_eq!(stream_partition_counts(reader(), chunk).unwrap(), counts);
+            }
+        }
+    }
+
+    #[test]
+    fn stream_errors() {
+        let spec = GraphSpec {
+            size: 100,
+            seed: graph::tests::TEST_SEED,
+            algo: DRGAlgo::Imported,
+        };
+        assert!(matches!(
+            generate(spec, 10, Vec::new()),
+            Err(StreamError::Unsupported(DRGAlgo::Imported))
+        ));
+
+        assert!(matches!(
+            ParentsReader::new(&b"DRGX\x01"[..]),
+            Err(StreamError::Format(_))
+        ));
+        let spec = GraphSpec {
+            algo: DRGAlgo::MetaBucket(3),
+            ..spec
+        };
+        let mut bytes = Vec::new();
+        generate(spec, 10, &mut bytes).unwrap();
+        let truncated = ParentsReader::new(&bytes[..bytes.len() - 1]).unwrap();
+        assert!(matches!(
+            truncated.read_graph(),
+            Err(StreamError::Io(ref e)) if e.kind() == io::ErrorKind::UnexpectedEof
+        ));
+
+        // exclusion set of another graph
+        let g = ParentsReader::new(&bytes[..])
+            .unwrap()
+            .read_graph()
+            .unwrap();
+        let other = Graph::new(50, graph::tests::TEST_SEED, DRGAlgo::MetaBucket(3)).unwrap();
+        let s = ExclusionSet::new(&other);
+        assert!(matches!(
+            stream_depth(ParentsReader::new(&bytes[..]).unwrap(), 10, Some(&s)),
+            Err(StreamError::ExclusionSet(ExclusionSetError::Len {
+                expected: 100,
+                len: 50
+            }))
+        ));
+        assert_eq!(
+            stream_depth(
+                ParentsReader::new(&bytes[..]).unwrap(),
+                10,
+                Some(&ExclusionSet::new(&g))
+            )
+            .unwrap(),
+            g.depth()
+        );
+
+        // more parents than nodes before it, and a header longer than the input
+        let mut header = Vec::new();
+        header.extend_from_slice(MAGIC);
+        header.push(VERSION);
+        let json = serde_json::to_vec(&spec).unwrap();
+        write_varint(&mut header, json.len());
+        header.extend_from_slice(&json);
+        let mut bytes = header.clone();
+        write_varint(&mut bytes, 0);
+        write_varint(&mut bytes, usize::MAX);
+        assert!(matches!(
+            ParentsReader::new(&bytes[..]).unwrap().read_graph(),
+            Err(StreamError::Format(_))
+        ));
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        write_varint(&mut bytes, usize::MAX);
+        assert!(matches!(
+            ParentsReader::new(&bytes[..]),
+            Err(StreamError::Io(ref e)) if e.kind() == io::ErrorKind::UnexpectedEof
+        ));
+    }
+}
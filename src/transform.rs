@@ -0,0 +1,190 @@
+use std::ops::Range;
+
+use crate::error::Error;
+use crate::graph::{ExclusionSet, Graph, Node, NodeIndex};
+
+/// Indegree reduction of a graph as described in Lemma 1 of [ABP17
+/// paper](https://eprint.iacr.org/2016/875.pdf): each node `v` with in-degree
+/// `δ` is replaced by a path of `δ` nodes `(v,0) -> ... -> (v,δ-1)`, where the
+/// `i`-th node of the path receives the edge coming from the `i`-th parent of
+/// `v` (from the *last* node of that parent's path). The resulting graph has an
+/// in-degree of at most 2 and keeps a proper labelling since the paths are laid
+/// out in the order of the original nodes.
+/// Nodes without parents are kept as a path of a single node.
+#[derive(Debug)]
+pub struct IndegreeReduction<I = Node> {
+    graph: Graph<I>,
+    // the path of the original node `v` spans `offsets[v]..offsets[v + 1]`
+    // in the reduced graph
+    offsets: Vec<Node>,
+    // original node of each node of the reduced graph
+    origins: Vec<Node>,
+}
+
+impl<I: NodeIndex> IndegreeReduction<I> {
+    /// Fails with `Error::IndexOverflow` if the reduced graph, which has a
+    /// node per edge of `g`, doesn't fit in `I`.
+    pub fn new(g: &Graph<I>) -> Result<Self, Error> {
+        let mut offsets = Vec::with_capacity(g.size() + 1);
+        let mut origins = Vec::with_capacity(g.count_edges());
+        offsets.push(0);
+        for (node, parents) in g.parents().iter().enumerate() {
+            let length = std::cmp::max(parents.len(), 1);
+            origins.extend(std::iter::repeat_n(node, length));
+            offsets.push(offsets[node] + length);
+        }
+
+        let mut parents = Vec::with_capacity(origins.len());
+        for (node, node_parents) in g.parents().iter().enumerate() {
+            if node_parents.is_empty() {
+                parents.push(vec![]);
+                continue;
+            }
+            for (i, &parent) in node_parents.iter().enumerate() {
+                // the parent's path always ends before the current one starts
+                let mut expanded = vec![offsets[parent.node() + 1] - 1];
+                if i > 0 {
+                    expanded.push(offsets[node] + i - 1);
+                }
+                parents.push(expanded);
+            }
+        }
+
+        Ok(IndegreeReduction {
+            graph: g.derive(parents)?,
+            offsets,
+            origins,
+        })
+    }
+
+    /// Reduced graph.
+    pub fn graph(&self) -> &Graph<I> {
+        &self.graph
+    }
+
+    pub fn graph_mut(&mut self) -> &mut Graph<I> {
+        &mut self.graph
+    }
+
+    /// Number of nodes in the original graph.
+    pub fn original_size(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    /// Original node that the `node` of the reduced graph belongs to.
+    pub fn original(&self, node: Node) -> Node {
+        self.origins[node]
+    }
+
+    /// Nodes of the reduced graph forming the path of the original `node`.
+    pub fn expanded(&self, node: Node) -> Range<Node> {
+        self.offsets[node]..self.offsets[node + 1]
+    }
+
+    /// Last node of the path of the original `node`, the only one with edges
+    /// going out of the path.
+    pub fn output(&self, node: Node) -> Node {
+        self.offsets[node + 1] - 1
+    }
+
+    /// Project an exclusion set of the reduced graph back onto the original
+    /// graph: an original node is excluded if any node of its path is. The
+    /// projected set is never bigger than `s` and, since any path of the
+    /// original graph maps to a longer path in the reduced graph,
+    /// `depth(G - project(S)) <= depth(G' - S)`.
+    pub fn project(&self, s: &ExclusionSet) -> ExclusionSet {
+        let mut projected = ExclusionSet::new_with_size(self.original_size());
+        for node in 0..self.origins.len() {
+            if s.contains(node) {
+                projected.insert(self.original(node));
+            }
+        }
+        projected
+    }
+
+    /// Map an exclusion set of the original graph onto the reduced graph by
+    /// excluding the entire path of each original node.
+    pub fn expand(&self, s: &ExclusionSet) -> ExclusionSet {
+        let mut expanded = ExclusionSet::new(&self.graph);
+        for node in 0..self.original_size() {
+            if s.contains(node) {
+                self.expanded(node).for_each(|n| expanded.insert(n));
+            }
+        }
+        expanded
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::{self, DRGAlgo, GraphSpec};
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaChaRng;
+
+    #[test]
+    fn indegree_reduction() {
+        // 0 -> 1 -> 2 -> 3, 0 -> 2, 0 -> 3, 1 -> 3
+        let parents = vec![vec![], vec![0], vec![0, 1], vec![0, 1, 2]];
+        let g = graph::tests::graph_from(parents);
+        let r = IndegreeReduction::new(&g).unwrap();
+        assert_eq!(r.graph().size(), 7);
+        assert_eq!(
+            r.graph().parents(),
+            &vec![
+                vec![],
+                vec![0],
+                vec![0],
+                vec![1, 2],
+                vec![0],
+                vec![1, 4],
+                vec![3, 5],
+            ]
+        );
+        assert_eq!(r.expanded(2), 2..4);
+        assert_eq!(r.output(2), 3);
+        assert_eq!(r.original(5), 3);
+        // the path 0 -> 1 -> 2 -> 3 becomes 0 -> 1 -> 3 -> 6
+        assert_eq!(r.graph().depth(), 3);
+    }
+
+    #[test]
+    fn indegree_reduction_projection() {
+        let size = 1 << 8;
+        let g = Graph::new(size, graph::tests::TEST_SEED, DRGAlgo::MetaBucket(6)).unwrap();
+        let r = IndegreeReduction::new(&g).unwrap();
+        assert!(r.graph().parents().iter().all(|p| p.len() <= 2));
+        assert!(r.graph().depth() >= g.depth());
+
+        let mut rng = ChaChaRng::from_seed(graph::tests::TEST_SEED);
+        let mut s = ExclusionSet::new(r.graph());
+        for _ in 0..r.graph().size() / 10 {
+            s.insert(rng.gen_range(0, r.graph().size()));
+        }
+        let projected = r.project(&s);
+        assert!(projected.size() <= s.size());
+        assert!(g.depth_exclude(&projected) <= r.graph().depth_exclude(&s));
+        assert_eq!(r.project(&r.expand(&projected)), projected);
+    }
+
+    #[test]
+    fn indegree_reduction_node_index() {
+        // same reduction of a graph stored with u32 nodes
+        let spec = GraphSpec {
+            size: 1 << 8,
+            seed: graph::tests::TEST_SEED,
+            algo: DRGAlgo::MetaBucket(6),
+        };
+        let g = Graph::new_from_rng(spec, &mut ChaChaRng::from_seed(spec.seed)).unwrap();
+        let g32 = Graph::<u32>::generate(spec, &mut ChaChaRng::from_seed(spec.seed)).unwrap();
+        let r = IndegreeReduction::new(&g).unwrap();
+        let r32 = IndegreeReduction::new(&g32).unwrap();
+        assert_eq!(r32.graph().size(), r.graph().size());
+        assert!(r32
+            .graph()
+            .parents()
+            .iter()
+            .zip(r.graph().parents().iter())
+            .all(|(p32, p)| p32.iter().map(|n| n.node()).eq(p.iter().cloned())));
+    }
+}
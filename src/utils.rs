@@ -1,31 +1,25 @@
-use crate::graph::Edge;
+use crate::graph::{Edge, NodeIndex};
 
-// msbd returns the most significant different bit index between the two given
-// numbers. The index is 0-based counting from LSB to MSB.
-// if u and v are equal, it returns an index higher than than the bitsize.
-// TODO: make that generic for xorable values ?
-// FIXME: Make the return type a bit position (to help check for overflows with
-// the node type).
-pub fn msbd(edge: &Edge) -> usize {
-    let bitsize = node_bitsize();
-    let xor = edge.parent ^ edge.child;
+// msbd returns the most significant different bit index between the parent
+// and the child of the edge. The index is 0-based counting from LSB to MSB, so
+// it's always below `node_bitsize::<I>()` (the number of msbd partitions of the
+// edges of a graph indexed with `I`).
+// If both nodes are equal, it returns `node_bitsize::<I>()`, out of bounds.
+pub fn msbd<I: NodeIndex>(edge: &Edge<I>) -> usize {
+    let bitsize = node_bitsize::<I>();
+    let xor = edge.parent.node() ^ edge.child.node();
     if xor == 0 {
-        return bitsize + 1;
-        // FIXME: The +1 seems unnecessary, for a 0-based index
-        // the size is already out of bounds.
+        return bitsize;
     }
 
-    let mut idx = bitsize - 1; // index of the different bit
-    loop {
-        if xor & (1 << idx) > 0 {
-            return idx;
-        }
-        idx -= 1;
-    }
+    // index of the different bit
+    let bit = usize::BITS as usize - 1 - xor.leading_zeros() as usize;
+    debug_assert!(bit < bitsize, "msbd {} overflows the node type", bit);
+    bit
 }
 
-pub fn node_bitsize() -> usize {
-    std::mem::size_of::<usize>() * 8
+pub fn node_bitsize<I: NodeIndex>() -> usize {
+    I::BITS
 }
 
 pub fn to_hex_string(bytes: &[u8]) -> String {
@@ -39,9 +33,18 @@ mod test {
 
     #[test]
     fn test_msbd() {
-        assert_eq!(msbd(&Edge::new(2, 4)), 2);
-        assert_eq!(msbd(&Edge::new(0, 2)), 1);
-        assert_eq!(msbd(&Edge::new(0, 1)), 0);
-        assert_eq!(msbd(&Edge::new(2, 3)), 0);
+        assert_eq!(msbd(&Edge::new(2usize, 4)), 2);
+        assert_eq!(msbd(&Edge::new(0usize, 2)), 1);
+        assert_eq!(msbd(&Edge::new(0usize, 1)), 0);
+        assert_eq!(msbd(&Edge::new(2usize, 3)), 0);
+        assert_eq!(
+            msbd(&Edge {
+                parent: 3u32,
+                child: 3
+            }),
+            node_bitsize::<u32>()
+        );
+        assert_eq!(msbd(&Edge::new(1u32 << 31, u32::MAX)), 30);
+        assert_eq!(msbd(&Edge::new(0u64, 1 << 63)), 63);
     }
 }